@@ -1,32 +1,32 @@
 use serde::{Serialize, Deserialize};
 use kore_contract_sdk as sdk;
+use sdk::schema::JsonSchema;
 
 /// Define the state of the contract. 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+#[schemars(crate = "kore_contract_sdk::schema::schemars")]
 struct State {
   pub one: u32,
   pub two: u32,
   pub three: u32
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[schemars(crate = "kore_contract_sdk::schema::schemars")]
 enum StateEvent {
-  ModOne { data: u32 },
-  ModTwo { data: u32 },
-  ModThree { data: u32 },
-  ModAll { one: u32, two: u32, three: u32 }
-}
-
-#[unsafe(no_mangle)]
-pub unsafe fn main_function(state_ptr: i32, init_state_ptr: i32, event_ptr: i32, is_owner: i32) -> u32 {
-  sdk::execute_contract(state_ptr, init_state_ptr, event_ptr, is_owner, contract_logic)
+  One { data: u32 },
+  Two { data: u32 },
+  Three { data: u32 },
+  All { one: u32, two: u32, three: u32 }
 }
 
-#[unsafe(no_mangle)]
-pub unsafe fn init_check_function(state_ptr: i32) -> u32 {
-  sdk::check_init_data(state_ptr, init_logic)
+#[derive(Serialize, Deserialize)]
+enum StateQuery {
+  Sum,
+  Max
 }
 
+#[sdk::contract(init)]
 fn init_logic(
   _state: &State,
   contract_result: &mut sdk::ContractInitCheck,
@@ -34,27 +34,28 @@ fn init_logic(
   contract_result.success = true;
 }
 
+#[sdk::contract(schema)]
 fn contract_logic(
   context: &sdk::Context<State, StateEvent>,
   contract_result: &mut sdk::ContractResult<State>,
 ) {
   let state = &mut contract_result.final_state;
   match context.event {
-      StateEvent::ModOne { data } => {
+      StateEvent::One { data } => {
         state.one = data;
       },
-      StateEvent::ModTwo { data } => {
+      StateEvent::Two { data } => {
         state.two = data;
       },
-      StateEvent::ModThree { data } => {
+      StateEvent::Three { data } => {
         if data == 50 {
-          contract_result.error = "Can not change three value, 50 is a invalid value".to_owned();
+          contract_result.error = Some("Can not change three value, 50 is a invalid value".into());
           return
         }
         
         state.three = data;
       },
-      StateEvent::ModAll { one, two, three } => {
+      StateEvent::All { one, two, three } => {
         state.one = one;
         state.two = two;
         state.three = three;
@@ -63,6 +64,47 @@ fn contract_logic(
   contract_result.success = true;
 }
 
+/// Same logic as `contract_logic`, returning a `Result` instead of filling a `ContractResult`.
+/// A contract exports one of them, annotating it with `#[sdk::contract]`, so this one is only
+/// built for the tests below, which run it through `testing::Invocation`.
+#[cfg(test)]
+fn result_contract_logic(
+  context: &sdk::Context<State, StateEvent>,
+  state: &mut State,
+) -> Result<(), sdk::ContractError> {
+  match context.event {
+      StateEvent::One { data } => {
+        state.one = data;
+      },
+      StateEvent::Two { data } => {
+        state.two = data;
+      },
+      StateEvent::Three { data } => {
+        sdk::ensure!(data != 50, "Can not change three value, 50 is a invalid value");
+        state.three = data;
+      },
+      StateEvent::All { one, two, three } => {
+        state.one = one;
+        state.two = two;
+        state.three = three;
+        sdk::debug!("All values changed"; one = one, two = two, three = three);
+      }
+  }
+  Ok(())
+}
+
+#[sdk::contract(query)]
+fn query_logic(state: &State, query: &StateQuery) -> Result<u32, sdk::ContractError> {
+  let values = [state.one, state.two, state.three];
+  match query {
+    StateQuery::Sum => values
+      .iter()
+      .try_fold(0u32, |sum, value| sum.checked_add(*value))
+      .ok_or_else(|| "The sum overflows".into()),
+    StateQuery::Max => Ok(values.into_iter().max().unwrap_or_default())
+  }
+}
+
 #[test]
 fn contract_test() {
   let initial_state = State {
@@ -72,8 +114,13 @@ fn contract_test() {
   };
   let context = sdk::Context {
     initial_state: initial_state.clone(),
-    event: StateEvent::ModOne { data: 100 },
-    is_owner: false
+    event: StateEvent::One { data: 100 },
+    is_owner: false,
+    signer: "signer".to_owned(),
+    subject_id: "subject".to_owned(),
+    governance_id: "governance".to_owned(),
+    sn: 1,
+    timestamp: 0
   };
   let mut result = sdk::ContractResult::new(initial_state);
   contract_logic(&context, &mut result);
@@ -90,12 +137,110 @@ fn contract_test_fail() {
   };
   let context = sdk::Context {
     initial_state: initial_state.clone(),
-    event: StateEvent::ModThree { data: 50 },
-    is_owner: false
+    event: StateEvent::Three { data: 50 },
+    is_owner: false,
+    signer: "signer".to_owned(),
+    subject_id: "subject".to_owned(),
+    governance_id: "governance".to_owned(),
+    sn: 1,
+    timestamp: 0
   };
   let mut result = sdk::ContractResult::new(initial_state);
   contract_logic(&context, &mut result);
   assert_eq!(result.final_state.three, 3);
-  assert_eq!(result.error, "Can not change three value, 50 is a invalid value");
+  assert_eq!(result.error.unwrap().message, "Can not change three value, 50 is a invalid value");
   assert!(!result.success);
 }
+
+#[test]
+fn result_contract_test() {
+  let initial_state = State {
+    one: 1,
+    two: 2,
+    three: 3
+  };
+  let context = sdk::Context {
+    initial_state: initial_state.clone(),
+    event: StateEvent::One { data: 100 },
+    is_owner: false,
+    signer: "signer".to_owned(),
+    subject_id: "subject".to_owned(),
+    governance_id: "governance".to_owned(),
+    sn: 1,
+    timestamp: 0
+  };
+  let mut state = initial_state;
+  assert!(result_contract_logic(&context, &mut state).is_ok());
+  assert_eq!(state.one, 100);
+}
+
+#[test]
+fn result_contract_test_fail() {
+  let initial_state = State {
+    one: 1,
+    two: 2,
+    three: 3
+  };
+  let context = sdk::Context {
+    initial_state: initial_state.clone(),
+    event: StateEvent::Three { data: 50 },
+    is_owner: false,
+    signer: "signer".to_owned(),
+    subject_id: "subject".to_owned(),
+    governance_id: "governance".to_owned(),
+    sn: 1,
+    timestamp: 0
+  };
+  let mut state = initial_state;
+  let error = result_contract_logic(&context, &mut state).unwrap_err();
+  assert_eq!(state.three, 3);
+  assert_eq!(error.message, "Can not change three value, 50 is a invalid value");
+}
+
+#[test]
+fn contract_abi_test() {
+  let initial_state = State {
+    one: 1,
+    two: 2,
+    three: 3
+  };
+  let result = sdk::testing::Invocation::new(initial_state, StateEvent::All { one: 10, two: 20, three: 30 })
+    .run(contract_logic);
+  let final_state = result.final_state.unwrap();
+  assert_eq!((final_state.one, final_state.two, final_state.three), (10, 20, 30));
+  assert!(result.success);
+}
+
+#[test]
+fn result_contract_abi_test() {
+  let initial_state = State {
+    one: 1,
+    two: 2,
+    three: 3
+  };
+  let result = sdk::testing::Invocation::new(initial_state, StateEvent::Three { data: 50 })
+    .run(result_contract_logic);
+  assert_eq!(result.final_state.unwrap().three, 3);
+  assert!(!result.success);
+  assert_eq!(result.error.unwrap().message, "Can not change three value, 50 is a invalid value");
+}
+
+#[test]
+fn query_test() {
+  let state = State {
+    one: 1,
+    two: 20,
+    three: 3
+  };
+  let result = sdk::testing::run_query(&state, &StateQuery::Sum, query_logic);
+  assert_eq!(result.response, Some(24));
+  let result = sdk::testing::run_query(&state, &StateQuery::Max, query_logic);
+  assert_eq!(result.response, Some(20));
+}
+
+#[test]
+fn contract_schema_test() {
+  let schema = sdk::schema::ContractSchema::new::<State, StateEvent>();
+  assert_eq!(schema.state["required"].as_array().unwrap().len(), 3);
+  assert_eq!(schema.event["oneOf"].as_array().unwrap().len(), 4);
+}
@@ -12,16 +12,7 @@ enum StateEvent {
   ChangeData { data: String },
 }
 
-#[unsafe(no_mangle)]
-pub unsafe fn main_function(state_ptr: i32, init_state_ptr: i32, event_ptr: i32, is_owner: i32) -> u32 {
-  sdk::execute_contract(state_ptr, init_state_ptr, event_ptr, is_owner, contract_logic)
-}
-
-#[unsafe(no_mangle)]
-pub unsafe fn init_check_function(state_ptr: i32) -> u32 {
-  sdk::check_init_data(state_ptr, init_logic)
-}
-
+#[sdk::contract(init)]
 fn init_logic(
   _state: &State,
   contract_result: &mut sdk::ContractInitCheck,
@@ -29,6 +20,7 @@ fn init_logic(
   contract_result.success = true;
 }
 
+#[sdk::contract]
 fn contract_logic(
   context: &sdk::Context<State, StateEvent>,
   contract_result: &mut sdk::ContractResult<State>,
@@ -51,7 +43,12 @@ fn contract_test() {
   let context = sdk::Context {
     initial_state: initial_state.clone(),
     event: StateEvent::ChangeData { data: "KoreLedger".to_owned() },
-    is_owner: false
+    is_owner: false,
+    signer: "signer".to_owned(),
+    subject_id: "subject".to_owned(),
+    governance_id: "governance".to_owned(),
+    sn: 1,
+    timestamp: 0
   };
   let mut result = sdk::ContractResult::new(initial_state);
   contract_logic(&context, &mut result);
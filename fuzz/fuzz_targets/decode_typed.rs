@@ -0,0 +1,47 @@
+// Copyright 2025 Kore Ledger
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Deserializes arbitrary bytes into a contract-like state with the typed deserializer.
+//!
+//! Decoding must never panic, and accepted input must encode back to a state that decodes to
+//! the same value.
+
+#![no_main]
+
+use std::collections::BTreeMap;
+
+use kore_contract_sdk::{Decimal, DecodeLimits, U256, de::from_bytes_with_limits, to_bytes};
+use libfuzzer_sys::fuzz_target;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Event {
+    Reset,
+    Set(u32),
+    Move { x: i64, y: i64 },
+    Nested(Vec<Event>),
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct State<'a> {
+    name: &'a str,
+    owner: Option<String>,
+    balances: BTreeMap<u32, Decimal<2>>,
+    supply: U256,
+    events: Vec<Event>,
+    ratio: f64,
+}
+
+fuzz_target!(|data: &[u8]| {
+    let limits = DecodeLimits {
+        max_depth: 32,
+        max_elements: 1024,
+        max_string_len: 4096,
+        max_size: 1 << 16,
+    };
+    if let Ok(state) = from_bytes_with_limits::<State>(data, limits) {
+        let bytes = to_bytes(&state).expect("A decoded state can be encoded");
+        let again = from_bytes_with_limits::<State>(&bytes, limits);
+        assert_eq!(again.as_ref(), Ok(&state));
+    }
+});
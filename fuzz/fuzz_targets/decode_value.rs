@@ -0,0 +1,25 @@
+// Copyright 2025 Kore Ledger
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Decodes arbitrary bytes as a `ValueWrapper`.
+//!
+//! Decoding must never panic nor exceed the default limits, both decoders must agree, and a
+//! decoded value must survive an encode and decode round trip.
+
+#![no_main]
+
+use borsh::BorshDeserialize;
+use kore_contract_sdk::{ValueWrapper, from_bytes};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let decoded = ValueWrapper::try_from_slice(data);
+    let typed = from_bytes::<serde_json::Value>(data);
+    assert_eq!(decoded.as_ref().ok().map(|value| &value.0), typed.as_ref().ok());
+
+    if let Ok(value) = decoded {
+        let bytes = borsh::to_vec(&value).expect("A decoded value can be encoded");
+        let again = ValueWrapper::try_from_slice(&bytes).expect("An encoded value can be decoded");
+        assert_eq!(again, value);
+    }
+});
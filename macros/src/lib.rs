@@ -0,0 +1,946 @@
+// Copyright 2025 Kore Ledger
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Procedural macros for the Kore Ledger contract SDK.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{ToTokens, quote};
+use syn::{
+    FnArg, GenericArgument, ItemFn, PathArguments, ReturnType, Type, TypePath, TypeReference,
+    parse2, spanned::Spanned,
+};
+
+/// Generates the wasm entry points of a contract.
+///
+/// Applied to the contract logic, with signature
+/// `fn(&Context<State, Event>, &mut ContractResult<State>)`,
+/// `fn(&Context<State, Event>) -> Result<State, ContractError>` or
+/// `fn(&Context<State, Event>, &mut State) -> Result<(), ContractError>`, optionally with a
+/// last `&mut Outputs` argument in the `Result` forms, it exports
+/// `main_function`, which calls `execute_contract`. Its signature follows the host ABI of the
+/// SDK: with the `legacy-abi` feature it does not receive the execution information. With
+/// `#[contract(options = path)]` it calls `execute_contract_with` using the `ExecutionOptions`
+/// returned by the function at `path`. With `#[contract(schema)]` it also exports
+/// `schema_function`, which returns the JSON Schemas of `State` and `Event` and requires the
+/// `schema` feature of the SDK. With `#[contract(batch)]` it also exports `batch_function`,
+/// which calls `execute_contract_batch` with the same options.
+///
+/// Applied as `#[contract(init)]` to the init logic, with signature
+/// `fn(&State, &mut ContractInitCheck)` or `fn(&State) -> Result<(), ContractError>`, it
+/// exports `init_check_function`, which calls `check_init_data`. With signature
+/// `fn(&InitContext<State>) -> Result<State, ContractError>` it exports `init_function`
+/// instead, which calls `init_contract` and returns the initial state. With
+/// `#[contract(init, float_policy = path)]` they call `check_init_data_with` or
+/// `init_contract_with` using the `FloatPolicy` at `path`. The constructor also accepts
+/// `#[contract(init, options = path)]`, which calls `init_contract_with` using the
+/// `ExecutionOptions` returned by the function at `path`, so the initial state is stored with
+/// the version of its migrations.
+///
+/// Applied as `#[contract(query)]` to a query logic, with signature
+/// `fn(&State, &Query) -> Result<Response, ContractError>`, it exports `query_function`, which
+/// calls `execute_query`.
+///
+/// The generated code refers to the SDK as `::kore_contract_sdk`. Contracts that depend on it
+/// under another name, or re-export it from their own crate, pass its path with
+/// `#[contract(crate = path)]`, which can be combined with any of the arguments above.
+#[proc_macro_attribute]
+pub fn contract(args: TokenStream, item: TokenStream) -> TokenStream {
+    expand(args.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Entry point requested through the macro arguments.
+enum EntryPoint {
+    /// `main_function`, optionally run with the `ExecutionOptions` returned by a function,
+    /// and `schema_function` and `batch_function` if requested.
+    Main {
+        options: Option<syn::Path>,
+        schema: bool,
+        batch: bool,
+    },
+    /// `init_check_function` or `init_function`, optionally run with a `FloatPolicy`, or with
+    /// the `ExecutionOptions` returned by a function for `init_function`.
+    Init {
+        float_policy: Option<syn::Path>,
+        options: Option<(Span, syn::Path)>,
+    },
+    /// `query_function`.
+    Query,
+}
+
+impl EntryPoint {
+    /// Parses the macro arguments into the entry point and the path of the SDK crate.
+    fn parse(args: TokenStream2) -> syn::Result<(Self, syn::Path)> {
+        let mut sdk = None;
+        let mut init = None;
+        let mut query = None;
+        let mut options = None;
+        let mut schema = false;
+        let mut batch = false;
+        let mut float_policy = None;
+        let parser = syn::meta::parser(|meta| {
+            if meta.path.is_ident("init") {
+                init = Some(meta.path.span());
+                Ok(())
+            } else if meta.path.is_ident("query") {
+                query = Some(meta.path.span());
+                Ok(())
+            } else if meta.path.is_ident("options") {
+                options = Some((meta.path.span(), meta.value()?.parse()?));
+                Ok(())
+            } else if meta.path.is_ident("schema") {
+                schema = true;
+                Ok(())
+            } else if meta.path.is_ident("batch") {
+                batch = true;
+                Ok(())
+            } else if meta.path.is_ident("float_policy") {
+                float_policy = Some((meta.path.span(), meta.value()?.parse()?));
+                Ok(())
+            } else if meta.path.is_ident("crate") {
+                sdk = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error(
+                    "Unknown contract entry point, expected `#[contract]`, \
+                     `#[contract(options = path, schema, batch)]`, \
+                     `#[contract(init, float_policy = path)]`, `#[contract(init, options = path)]` \
+                     or `#[contract(query)]`, optionally with `crate = path`",
+                ))
+            }
+        });
+        syn::parse::Parser::parse2(parser, args)?;
+        let sdk = sdk.unwrap_or_else(|| syn::parse_quote!(::kore_contract_sdk));
+        if let Some(span) = query {
+            if init.is_some() || options.is_some() || schema || batch || float_policy.is_some() {
+                return Err(syn::Error::new(
+                    span,
+                    "`query` can not be used with other contract arguments",
+                ));
+            }
+            return Ok((Self::Query, sdk));
+        }
+        let entry_point = match init {
+            Some(span) if schema || batch => Err(syn::Error::new(
+                span,
+                "`schema` and `batch` can not be used with the init entry point",
+            )),
+            Some(span) if options.is_some() && float_policy.is_some() => Err(syn::Error::new(
+                span,
+                "`float_policy` can not be used with `options`, set it in the `options`",
+            )),
+            Some(_) => Ok(Self::Init {
+                float_policy: float_policy.map(|(_, path)| path),
+                options,
+            }),
+            None => match float_policy {
+                Some((span, _)) => Err(syn::Error::new(
+                    span,
+                    "`float_policy` can only be used with the init entry point, \
+                     set it in the `options` of the main entry point",
+                )),
+                None => Ok(Self::Main {
+                    options: options.map(|(_, path)| path),
+                    schema,
+                    batch,
+                }),
+            },
+        }?;
+        Ok((entry_point, sdk))
+    }
+}
+
+fn expand(args: TokenStream2, item: TokenStream2) -> syn::Result<TokenStream2> {
+    let (entry_point, sdk) = EntryPoint::parse(args)?;
+    let function: ItemFn = parse2(item)?;
+    check_plain_function(&function)?;
+    let ident = &function.sig.ident;
+    let shim = match entry_point {
+        EntryPoint::Main {
+            options,
+            schema,
+            batch,
+        } => {
+            let (state, event) = check_contract_signature(&function)?;
+            let schema = schema.then(|| {
+                quote! {
+                    /// Schema entry point exported to the Kore wasm runtime.
+                    ///
+                    /// # Safety
+                    ///
+                    /// Must be called by the host.
+                    #[unsafe(no_mangle)]
+                    pub unsafe fn schema_function() -> u32 {
+                        #sdk::schema::export_schema::<#state, #event>()
+                    }
+                }
+            });
+            let batch = batch.then(|| {
+                let options = match &options {
+                    Some(options) => quote!(#options()),
+                    None => quote!(#sdk::ExecutionOptions::default()),
+                };
+                quote! {
+                    /// Batch entry point exported to the Kore wasm runtime.
+                    ///
+                    /// # Safety
+                    ///
+                    /// Pointers must reference values allocated by the host.
+                    #[unsafe(no_mangle)]
+                    pub unsafe fn batch_function(
+                        state_ptr: i32,
+                        init_state_ptr: i32,
+                        events_ptr: i32,
+                        is_owner: i32,
+                        infos_ptr: i32,
+                    ) -> u32 {
+                        #sdk::execute_contract_batch(
+                            state_ptr,
+                            init_state_ptr,
+                            events_ptr,
+                            is_owner,
+                            infos_ptr,
+                            &#options,
+                            #ident,
+                        )
+                    }
+                }
+            });
+            let execute = match options {
+                Some(options) => quote! {
+                    #sdk::execute_contract_with(
+                        state_ptr,
+                        init_state_ptr,
+                        event_ptr,
+                        is_owner,
+                        info_ptr,
+                        &#options(),
+                        #ident,
+                    )
+                },
+                None => quote! {
+                    #sdk::execute_contract(
+                        state_ptr,
+                        init_state_ptr,
+                        event_ptr,
+                        is_owner,
+                        info_ptr,
+                        #ident,
+                    )
+                },
+            };
+            // The signature of `main_function` depends on the host ABI selected in the SDK.
+            quote! {
+                #[doc(hidden)]
+                fn __kore_main_function(
+                    state_ptr: i32,
+                    init_state_ptr: i32,
+                    event_ptr: i32,
+                    is_owner: i32,
+                    info_ptr: i32,
+                ) -> u32 {
+                    #execute
+                }
+
+                #sdk::export_main_function!(__kore_main_function);
+
+                #schema
+                #batch
+            }
+        }
+        EntryPoint::Init {
+            float_policy,
+            options,
+        } if is_constructor(&function) => {
+            check_constructor_signature(&function)?;
+            let init = match (float_policy, options) {
+                (_, Some((_, options))) => quote! {
+                    #sdk::init_contract_with(
+                        state_ptr,
+                        info_ptr,
+                        &#options(),
+                        #ident,
+                    )
+                },
+                (Some(float_policy), None) => quote! {
+                    #sdk::init_contract_with(
+                        state_ptr,
+                        info_ptr,
+                        &#sdk::ExecutionOptions::<_, ()>::default()
+                            .float_policy(#float_policy),
+                        #ident,
+                    )
+                },
+                (None, None) => quote! {
+                    #sdk::init_contract(state_ptr, info_ptr, #ident)
+                },
+            };
+            quote! {
+                /// Init entry point exported to the Kore wasm runtime.
+                ///
+                /// # Safety
+                ///
+                /// Pointers must reference values allocated by the host.
+                #[unsafe(no_mangle)]
+                pub unsafe fn init_function(state_ptr: i32, info_ptr: i32) -> u32 {
+                    #init
+                }
+            }
+        }
+        EntryPoint::Init {
+            float_policy,
+            options,
+        } => {
+            check_init_signature(&function)?;
+            if let Some((span, _)) = options {
+                return Err(syn::Error::new(
+                    span,
+                    "`options` can only be used with an init logic that returns the initial \
+                     state, `fn(&InitContext<State>) -> Result<State, ContractError>`",
+                ));
+            }
+            let check = match float_policy {
+                Some(float_policy) => quote! {
+                    #sdk::check_init_data_with(state_ptr, #float_policy, #ident)
+                },
+                None => quote! {
+                    #sdk::check_init_data(state_ptr, #ident)
+                },
+            };
+            quote! {
+                /// Init check entry point exported to the Kore wasm runtime.
+                ///
+                /// # Safety
+                ///
+                /// Pointers must reference values allocated by the host.
+                #[unsafe(no_mangle)]
+                pub unsafe fn init_check_function(state_ptr: i32) -> u32 {
+                    #check
+                }
+            }
+        }
+        EntryPoint::Query => {
+            check_query_signature(&function)?;
+            quote! {
+                /// Query entry point exported to the Kore wasm runtime.
+                ///
+                /// # Safety
+                ///
+                /// Pointers must reference values allocated by the host.
+                #[unsafe(no_mangle)]
+                pub unsafe fn query_function(state_ptr: i32, query_ptr: i32) -> u32 {
+                    #sdk::execute_query(state_ptr, query_ptr, #ident)
+                }
+            }
+        }
+    };
+    Ok(quote! {
+        #function
+        #shim
+    })
+}
+
+/// Rejects function shapes that can not be passed as a contract callback.
+fn check_plain_function(function: &ItemFn) -> syn::Result<()> {
+    let sig = &function.sig;
+    if let Some(asyncness) = &sig.asyncness {
+        return Err(syn::Error::new_spanned(
+            asyncness,
+            "Contract functions can not be async",
+        ));
+    }
+    if !sig.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &sig.generics,
+            "Contract functions can not be generic",
+        ));
+    }
+    Ok(())
+}
+
+/// Checks `fn(&Context<State, Event>, &mut ContractResult<State>)`,
+/// `fn(&Context<State, Event>) -> Result<State, ContractError>` or
+/// `fn(&Context<State, Event>, &mut State) -> Result<(), ContractError>`, the last two with an
+/// optional `&mut Outputs` argument, and returns the `State` and `Event` types.
+fn check_contract_signature(function: &ItemFn) -> syn::Result<(&Type, &Type)> {
+    const EXPECTED: &str = "Expected `fn(&Context<State, Event>, &mut ContractResult<State>)`, \
+         `fn(&Context<State, Event>) -> Result<State, ContractError>` or \
+         `fn(&Context<State, Event>, &mut State) -> Result<(), ContractError>`, \
+         the last two optionally with a last `&mut Outputs` argument";
+    let mut arguments = arguments(function)?;
+    if let ([_, _, ..], ReturnType::Type(..)) = (arguments.as_slice(), &function.sig.output) {
+        if reference_to(arguments[arguments.len() - 1], true, "Outputs", 0).is_ok() {
+            arguments.pop();
+        }
+    }
+    let (context, state) = match (arguments.as_slice(), &function.sig.output) {
+        ([context, result], ReturnType::Default) => {
+            let result_args = reference_to(result, true, "ContractResult", 1)?;
+            (context, result_args[0])
+        }
+        ([context], ReturnType::Type(_, output)) => (context, result_ok(output)?),
+        ([context, state], ReturnType::Type(_, output)) => {
+            let ok = result_ok(output)?;
+            if !is_unit(ok) {
+                return Err(syn::Error::new_spanned(
+                    ok,
+                    "Expected `Result<(), ContractError>`",
+                ));
+            }
+            (context, mutable_reference(state)?)
+        }
+        _ => return Err(syn::Error::new(function.sig.inputs.span(), EXPECTED)),
+    };
+    let context_args = reference_to(context, false, "Context", 2)?;
+    if !same_type(context_args[0], state) {
+        return Err(syn::Error::new_spanned(
+            state,
+            format!(
+                "The final state must match the `Context` state `{}`",
+                context_args[0].to_token_stream()
+            ),
+        ));
+    }
+    Ok((context_args[0], context_args[1]))
+}
+
+/// Checks `fn(&State, &mut ContractInitCheck)` or `fn(&State) -> Result<(), ContractError>`.
+fn check_init_signature(function: &ItemFn) -> syn::Result<()> {
+    const EXPECTED: &str = "Expected `fn(&State, &mut ContractInitCheck)` or \
+         `fn(&State) -> Result<(), ContractError>`";
+    let arguments = arguments(function)?;
+    let state = match (arguments.as_slice(), &function.sig.output) {
+        ([state, result], ReturnType::Default) => {
+            reference_to(result, true, "ContractInitCheck", 0)?;
+            state
+        }
+        ([state], ReturnType::Type(_, output)) => {
+            let ok = result_ok(output)?;
+            if !is_unit(ok) {
+                return Err(syn::Error::new_spanned(
+                    ok,
+                    "Expected `Result<(), ContractError>`",
+                ));
+            }
+            state
+        }
+        _ => return Err(syn::Error::new(function.sig.inputs.span(), EXPECTED)),
+    };
+    match state {
+        Type::Reference(reference) if reference.mutability.is_none() => Ok(()),
+        _ => Err(syn::Error::new_spanned(state, "Expected `&State`")),
+    }
+}
+
+/// Whether the init logic takes an `InitContext`, as a constructor.
+fn is_constructor(function: &ItemFn) -> bool {
+    function.sig.inputs.len() == 1
+        && arguments(function)
+            .is_ok_and(|arguments| reference_to(arguments[0], false, "InitContext", 1).is_ok())
+}
+
+/// Checks `fn(&InitContext<State>) -> Result<State, ContractError>`.
+fn check_constructor_signature(function: &ItemFn) -> syn::Result<()> {
+    let arguments = arguments(function)?;
+    let context_args = reference_to(arguments[0], false, "InitContext", 1)?;
+    let ReturnType::Type(_, output) = &function.sig.output else {
+        return Err(syn::Error::new(
+            function.sig.span(),
+            "Expected `fn(&InitContext<State>) -> Result<State, ContractError>`",
+        ));
+    };
+    let state = result_ok(output)?;
+    if !same_type(context_args[0], state) {
+        return Err(syn::Error::new_spanned(
+            state,
+            format!(
+                "The initial state must match the `InitContext` state `{}`",
+                context_args[0].to_token_stream()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks `fn(&State, &Query) -> Result<Response, ContractError>`.
+fn check_query_signature(function: &ItemFn) -> syn::Result<()> {
+    let arguments = arguments(function)?;
+    let ([state, query], ReturnType::Type(_, output)) =
+        (arguments.as_slice(), &function.sig.output)
+    else {
+        return Err(syn::Error::new(
+            function.sig.span(),
+            "Expected `fn(&State, &Query) -> Result<Response, ContractError>`",
+        ));
+    };
+    for (argument, expected) in [(state, "Expected `&State`"), (query, "Expected `&Query`")] {
+        match argument {
+            Type::Reference(reference) if reference.mutability.is_none() => {}
+            _ => return Err(syn::Error::new_spanned(argument, expected)),
+        }
+    }
+    result_ok(output)?;
+    Ok(())
+}
+
+fn arguments(function: &ItemFn) -> syn::Result<Vec<&Type>> {
+    function
+        .sig
+        .inputs
+        .iter()
+        .map(|input| match input {
+            FnArg::Typed(pat) => Ok(pat.ty.as_ref()),
+            FnArg::Receiver(receiver) => Err(syn::Error::new_spanned(
+                receiver,
+                "Contract functions can not take `self`",
+            )),
+        })
+        .collect()
+}
+
+/// Checks that `ty` is `Result<T, ContractError>` and returns `T`.
+fn result_ok(ty: &Type) -> syn::Result<&Type> {
+    let expected = || syn::Error::new_spanned(ty, "Expected `Result<_, ContractError>`");
+    let Type::Path(TypePath { qself: None, path }) = ty else {
+        return Err(expected());
+    };
+    let Some(segment) = path.segments.last() else {
+        return Err(expected());
+    };
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return Err(expected());
+    };
+    let args = args.args.iter().collect::<Vec<_>>();
+    match args.as_slice() {
+        [
+            GenericArgument::Type(ok),
+            GenericArgument::Type(Type::Path(error)),
+        ] if segment.ident == "Result"
+            && error
+                .path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "ContractError") =>
+        {
+            Ok(ok)
+        }
+        _ => Err(expected()),
+    }
+}
+
+/// Checks that `ty` is `&mut T` and returns `T`.
+fn mutable_reference(ty: &Type) -> syn::Result<&Type> {
+    match ty {
+        Type::Reference(TypeReference {
+            mutability: Some(_),
+            elem,
+            ..
+        }) => Ok(elem),
+        _ => Err(syn::Error::new_spanned(ty, "Expected `&mut State`")),
+    }
+}
+
+fn is_unit(ty: &Type) -> bool {
+    matches!(ty, Type::Tuple(tuple) if tuple.elems.is_empty())
+}
+
+/// Checks that `ty` is a reference to `name<..>` with `arity` type arguments and returns them.
+fn reference_to<'a>(
+    ty: &'a Type,
+    mutable: bool,
+    name: &str,
+    arity: usize,
+) -> syn::Result<Vec<&'a Type>> {
+    let expected = || {
+        let reference = if mutable { "&mut " } else { "&" };
+        let params = match arity {
+            0 => "",
+            1 => "<State>",
+            _ => "<State, Event>",
+        };
+        syn::Error::new_spanned(ty, format!("Expected `{reference}{name}{params}`"))
+    };
+    let Type::Reference(TypeReference {
+        mutability, elem, ..
+    }) = ty
+    else {
+        return Err(expected());
+    };
+    if mutability.is_some() != mutable {
+        return Err(expected());
+    }
+    let Type::Path(TypePath { qself: None, path }) = elem.as_ref() else {
+        return Err(expected());
+    };
+    let Some(segment) = path.segments.last() else {
+        return Err(expected());
+    };
+    if segment.ident != name {
+        return Err(expected());
+    }
+    let args = match &segment.arguments {
+        PathArguments::None => Vec::new(),
+        PathArguments::AngleBracketed(args) => args
+            .args
+            .iter()
+            .map(|arg| match arg {
+                GenericArgument::Type(ty) => Ok(ty),
+                _ => Err(expected()),
+            })
+            .collect::<syn::Result<Vec<_>>>()?,
+        PathArguments::Parenthesized(_) => return Err(expected()),
+    };
+    if args.len() != arity {
+        return Err(expected());
+    }
+    Ok(args)
+}
+
+fn same_type(left: &Type, right: &Type) -> bool {
+    left.to_token_stream().to_string() == right.to_token_stream().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand_error(args: TokenStream2, item: TokenStream2) -> String {
+        expand(args, item).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_contract_entry_point() {
+        let tokens = expand(
+            quote!(),
+            quote! {
+                fn logic(context: &sdk::Context<State, Event>, result: &mut sdk::ContractResult<State>) {}
+            },
+        )
+        .unwrap()
+        .to_string();
+        assert!(tokens.contains("export_main_function ! (__kore_main_function)"));
+        assert!(tokens.contains("execute_contract"));
+
+        let tokens = expand(
+            quote!(options = contract_options),
+            quote! {
+                fn logic(context: &Context<State, Event>, result: &mut ContractResult<State>) {}
+            },
+        )
+        .unwrap()
+        .to_string();
+        assert!(tokens.contains("execute_contract_with"));
+        assert!(tokens.contains("contract_options ()"));
+        assert!(!tokens.contains("schema_function"));
+
+        let tokens = expand(
+            quote!(schema),
+            quote! {
+                fn logic(context: &Context<State, Event>, result: &mut ContractResult<State>) {}
+            },
+        )
+        .unwrap()
+        .to_string();
+        assert!(tokens.contains("export_schema :: < State , Event >"));
+        assert!(!tokens.contains("batch_function"));
+
+        let tokens = expand(
+            quote!(options = contract_options, batch),
+            quote! {
+                fn logic(context: &Context<State, Event>, result: &mut ContractResult<State>) {}
+            },
+        )
+        .unwrap()
+        .to_string();
+        assert!(tokens.contains("batch_function"));
+        assert!(tokens.contains("execute_contract_batch"));
+
+        let tokens = expand(
+            quote!(schema),
+            quote! {
+                fn logic(context: &Context<State, Event>) -> Result<State, sdk::ContractError> {}
+            },
+        )
+        .unwrap()
+        .to_string();
+        assert!(tokens.contains("export_schema :: < State , Event >"));
+
+        expand(
+            quote!(),
+            quote! {
+                fn logic(context: &Context<State, Event>, state: &mut State) -> Result<(), ContractError> {}
+            },
+        )
+        .unwrap();
+
+        let tokens = expand(
+            quote!(schema),
+            quote! {
+                fn logic(
+                    context: &Context<State, Event>,
+                    state: &mut State,
+                    outputs: &mut sdk::Outputs,
+                ) -> Result<(), ContractError> {}
+            },
+        )
+        .unwrap()
+        .to_string();
+        assert!(tokens.contains("export_schema :: < State , Event >"));
+
+        expand(
+            quote!(),
+            quote! {
+                fn logic(
+                    context: &Context<State, Event>,
+                    outputs: &mut Outputs,
+                ) -> Result<State, ContractError> {}
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_init_entry_point() {
+        let tokens = expand(
+            quote!(init),
+            quote! {
+                fn init(state: &State, result: &mut ContractInitCheck) {}
+            },
+        )
+        .unwrap()
+        .to_string();
+        assert!(tokens.contains("init_check_function"));
+        assert!(tokens.contains("check_init_data"));
+
+        let tokens = expand(
+            quote!(init, float_policy = sdk::FloatPolicy::Reject),
+            quote! {
+                fn init(state: &State, result: &mut ContractInitCheck) {}
+            },
+        )
+        .unwrap()
+        .to_string();
+        assert!(tokens.contains("check_init_data_with (state_ptr , sdk :: FloatPolicy :: Reject"));
+
+        let tokens = expand(
+            quote!(init, float_policy = FloatPolicy::Normalize),
+            quote! {
+                fn init(context: &sdk::InitContext<State>) -> Result<State, ContractError> {}
+            },
+        )
+        .unwrap()
+        .to_string();
+        assert!(tokens.contains("init_function (state_ptr : i32 , info_ptr : i32)"));
+        assert!(tokens.contains(". float_policy (FloatPolicy :: Normalize)"));
+        assert!(!tokens.contains("init_check_function"));
+
+        let tokens = expand(
+            quote!(init, options = contract_options),
+            quote! {
+                fn init(context: &InitContext<State>) -> Result<State, ContractError> {}
+            },
+        )
+        .unwrap()
+        .to_string();
+        assert!(
+            tokens.contains("init_contract_with (state_ptr , info_ptr , & contract_options ()")
+        );
+
+        expand(
+            quote!(init),
+            quote! {
+                fn init(state: &State) -> Result<(), ContractError> {}
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_query_entry_point() {
+        let tokens = expand(
+            quote!(query),
+            quote! {
+                fn query(state: &State, query: &Query) -> Result<Response, sdk::ContractError> {}
+            },
+        )
+        .unwrap()
+        .to_string();
+        assert!(tokens.contains("query_function (state_ptr : i32 , query_ptr : i32)"));
+        assert!(tokens.contains("execute_query (state_ptr , query_ptr , query)"));
+    }
+
+    #[test]
+    fn test_crate_path() {
+        let tokens = expand(
+            quote!(crate = sdk, batch),
+            quote! {
+                fn logic(context: &Context<State, Event>, result: &mut ContractResult<State>) {}
+            },
+        )
+        .unwrap()
+        .to_string();
+        assert!(tokens.contains("sdk :: export_main_function !"));
+        assert!(tokens.contains("sdk :: ExecutionOptions :: default ()"));
+        assert!(!tokens.contains("kore_contract_sdk"));
+
+        let tokens = expand(
+            quote!(init, float_policy = FloatPolicy::Reject, crate = crate::sdk),
+            quote! {
+                fn init(context: &InitContext<State>) -> Result<State, ContractError> {}
+            },
+        )
+        .unwrap()
+        .to_string();
+        assert!(tokens.contains("crate :: sdk :: init_contract_with"));
+        assert!(tokens.contains("crate :: sdk :: ExecutionOptions :: < _ , () >"));
+
+        let tokens = expand(
+            quote!(query, crate = ::my_sdk),
+            quote! {
+                fn query(state: &State, query: &Query) -> Result<Response, ContractError> {}
+            },
+        )
+        .unwrap()
+        .to_string();
+        assert!(tokens.contains(":: my_sdk :: execute_query"));
+    }
+
+    #[test]
+    fn test_invalid_signatures() {
+        let error = expand_error(
+            quote!(),
+            quote! {
+                fn logic(context: &Context<State, Event>, result: &mut ContractResult<Other>) {}
+            },
+        );
+        assert!(error.contains("must match"));
+
+        let error = expand_error(
+            quote!(),
+            quote! {
+                fn logic(context: &Context<State, Event>) -> Result<Other, ContractError> {}
+            },
+        );
+        assert!(error.contains("must match"));
+
+        let error = expand_error(
+            quote!(),
+            quote! {
+                fn logic(context: &Context<State, Event>, state: &mut State) -> Result<State, ContractError> {}
+            },
+        );
+        assert_eq!(error, "Expected `Result<(), ContractError>`");
+
+        let error = expand_error(
+            quote!(),
+            quote! {
+                fn logic(context: &Context<State, Event>, outputs: &mut Outputs) {}
+            },
+        );
+        assert!(error.contains("ContractResult"));
+
+        let error = expand_error(
+            quote!(),
+            quote! {
+                fn logic(context: &Context<State, Event>) -> Option<State> {}
+            },
+        );
+        assert_eq!(error, "Expected `Result<_, ContractError>`");
+
+        let error = expand_error(
+            quote!(),
+            quote! {
+                fn logic(context: &Context<State, Event>, result: &ContractResult<State>) {}
+            },
+        );
+        assert_eq!(error, "Expected `&mut ContractResult<State>`");
+
+        let error = expand_error(
+            quote!(),
+            quote! {
+                fn logic(context: &Context<State, Event>) {}
+            },
+        );
+        assert!(error.starts_with("Expected `fn(&Context<State, Event>"));
+
+        let error = expand_error(
+            quote!(init),
+            quote! {
+                fn init(state: &State, result: &mut ContractResult<State>) {}
+            },
+        );
+        assert_eq!(error, "Expected `&mut ContractInitCheck`");
+
+        let error = expand_error(
+            quote!(init),
+            quote! {
+                fn init(state: &mut State) -> Result<(), ContractError> {}
+            },
+        );
+        assert_eq!(error, "Expected `&State`");
+
+        let error = expand_error(quote!(view), quote! { fn logic() {} });
+        assert!(error.starts_with("Unknown contract entry point"));
+
+        let error = expand_error(
+            quote!(query),
+            quote! {
+                fn query(state: &mut State, query: &Query) -> Result<Response, ContractError> {}
+            },
+        );
+        assert_eq!(error, "Expected `&State`");
+
+        let error = expand_error(
+            quote!(init),
+            quote! {
+                fn init(context: &InitContext<State>) -> Result<Other, ContractError> {}
+            },
+        );
+        assert!(error.contains("must match the `InitContext` state"));
+
+        let error = expand_error(
+            quote!(init),
+            quote! {
+                fn init(context: &InitContext<State>) {}
+            },
+        );
+        assert!(error.starts_with("Expected `fn(&InitContext<State>)"));
+
+        let error = expand_error(
+            quote!(query),
+            quote! {
+                fn query(state: &State, query: &Query) -> Response {}
+            },
+        );
+        assert_eq!(error, "Expected `Result<_, ContractError>`");
+
+        let error = expand_error(quote!(query), quote! { fn query(state: &State) {} });
+        assert!(error.starts_with("Expected `fn(&State, &Query)"));
+
+        let error = expand_error(quote!(query, schema), quote! { fn logic() {} });
+        assert!(error.contains("can not be used with other contract arguments"));
+
+        let error = expand_error(
+            quote!(init, options = options),
+            quote! { fn init(state: &State) -> Result<(), ContractError> {} },
+        );
+        assert!(error.starts_with("`options` can only be used with an init logic"));
+
+        let error = expand_error(
+            quote!(init, options = options, float_policy = policy),
+            quote! { fn logic() {} },
+        );
+        assert!(error.contains("can not be used with `options`"));
+
+        let error = expand_error(quote!(init, schema), quote! { fn logic() {} });
+        assert!(error.contains("can not be used with the init entry point"));
+
+        let error = expand_error(quote!(init, batch), quote! { fn logic() {} });
+        assert!(error.contains("can not be used with the init entry point"));
+
+        let error = expand_error(quote!(float_policy = policy), quote! { fn logic() {} });
+        assert!(error.contains("can only be used with the init entry point"));
+    }
+}
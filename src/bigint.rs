@@ -0,0 +1,496 @@
+// Copyright 2025 Kore Ledger
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! 256-bit integers for amounts that do not fit in 64 or 128 bits.
+//!
+//! Like `Decimal`, they are serialized as strings and all their arithmetic is checked.
+
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{error::NumberError, numeric};
+
+/// Unsigned 256-bit integer.
+///
+/// Borsh encodes it as 32 little-endian bytes.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, BorshSerialize, BorshDeserialize)]
+pub struct U256([u64; 4]);
+
+impl U256 {
+    pub const ZERO: Self = Self([0; 4]);
+    pub const ONE: Self = Self([1, 0, 0, 0]);
+    pub const MAX: Self = Self([u64::MAX; 4]);
+
+    pub const fn from_u128(value: u128) -> Self {
+        Self([value as u64, (value >> 64) as u64, 0, 0])
+    }
+
+    /// Value as `u128`, or `None` if it does not fit.
+    pub fn to_u128(self) -> Option<u128> {
+        match self.0 {
+            [low, high, 0, 0] => Some(((high as u128) << 64) | low as u128),
+            _ => None,
+        }
+    }
+
+    pub fn is_zero(self) -> bool {
+        self == Self::ZERO
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        match self.overflowing_add(other) {
+            (sum, false) => Some(sum),
+            (_, true) => None,
+        }
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        match self.overflowing_sub(other) {
+            (difference, false) => Some(difference),
+            (_, true) => None,
+        }
+    }
+
+    pub fn checked_mul(self, other: Self) -> Option<Self> {
+        let mut product = [0u64; 8];
+        for (i, &left) in self.0.iter().enumerate() {
+            let mut carry = 0u128;
+            for (j, &right) in other.0.iter().enumerate() {
+                let partial = left as u128 * right as u128 + product[i + j] as u128 + carry;
+                product[i + j] = partial as u64;
+                carry = partial >> 64;
+            }
+            product[i + 4] = carry as u64;
+        }
+        match product {
+            [a, b, c, d, 0, 0, 0, 0] => Some(Self([a, b, c, d])),
+            _ => None,
+        }
+    }
+
+    pub fn checked_div(self, other: Self) -> Option<Self> {
+        self.div_rem(other).map(|(quotient, _)| quotient)
+    }
+
+    pub fn checked_rem(self, other: Self) -> Option<Self> {
+        self.div_rem(other).map(|(_, remainder)| remainder)
+    }
+
+    fn overflowing_add(self, other: Self) -> (Self, bool) {
+        let mut sum = [0u64; 4];
+        let mut carry = false;
+        for (i, limb) in sum.iter_mut().enumerate() {
+            let (partial, first) = self.0[i].overflowing_add(other.0[i]);
+            let (partial, second) = partial.overflowing_add(carry as u64);
+            *limb = partial;
+            carry = first || second;
+        }
+        (Self(sum), carry)
+    }
+
+    fn overflowing_sub(self, other: Self) -> (Self, bool) {
+        let mut difference = [0u64; 4];
+        let mut borrow = false;
+        for (i, limb) in difference.iter_mut().enumerate() {
+            let (partial, first) = self.0[i].overflowing_sub(other.0[i]);
+            let (partial, second) = partial.overflowing_sub(borrow as u64);
+            *limb = partial;
+            borrow = first || second;
+        }
+        (Self(difference), borrow)
+    }
+
+    fn bit(self, index: usize) -> bool {
+        (self.0[index / 64] >> (index % 64)) & 1 == 1
+    }
+
+    /// Quotient and remainder by binary long division, or `None` when dividing by zero.
+    fn div_rem(self, divisor: Self) -> Option<(Self, Self)> {
+        if divisor.is_zero() {
+            return None;
+        }
+        if let (Some(dividend), Some(divisor)) = (self.to_u128(), divisor.to_u128()) {
+            return Some((
+                Self::from_u128(dividend / divisor),
+                Self::from_u128(dividend % divisor),
+            ));
+        }
+        let mut quotient = Self::ZERO;
+        let mut remainder = Self::ZERO;
+        for index in (0..256).rev() {
+            // The shifted out bit makes the remainder greater than any divisor.
+            let carry = remainder.bit(255);
+            remainder = remainder.overflowing_add(remainder).0;
+            remainder.0[0] |= self.bit(index) as u64;
+            if carry || remainder >= divisor {
+                remainder = remainder.overflowing_sub(divisor).0;
+                quotient.0[index / 64] |= 1 << (index % 64);
+            }
+        }
+        Some((quotient, remainder))
+    }
+
+    fn div_rem_u64(self, divisor: u64) -> (Self, u64) {
+        let mut quotient = [0u64; 4];
+        let mut remainder = 0u128;
+        for i in (0..4).rev() {
+            let current = (remainder << 64) | self.0[i] as u128;
+            quotient[i] = (current / divisor as u128) as u64;
+            remainder = current % divisor as u128;
+        }
+        (Self(quotient), remainder as u64)
+    }
+}
+
+impl From<u64> for U256 {
+    fn from(value: u64) -> Self {
+        Self([value, 0, 0, 0])
+    }
+}
+
+impl From<u128> for U256 {
+    fn from(value: u128) -> Self {
+        Self::from_u128(value)
+    }
+}
+
+impl Ord for U256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+
+impl PartialOrd for U256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for U256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const CHUNK: u64 = 10_000_000_000_000_000_000;
+        let mut chunks = Vec::new();
+        let mut rest = *self;
+        loop {
+            let (quotient, chunk) = rest.div_rem_u64(CHUNK);
+            chunks.push(chunk);
+            if quotient.is_zero() {
+                break;
+            }
+            rest = quotient;
+        }
+        let mut chunks = chunks.iter().rev();
+        if let Some(first) = chunks.next() {
+            write!(f, "{}", first)?;
+        }
+        chunks.try_for_each(|chunk| write!(f, "{:019}", chunk))
+    }
+}
+
+impl fmt::Debug for U256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "U256({})", self)
+    }
+}
+
+impl FromStr for U256 {
+    type Err = NumberError;
+
+    /// Parses decimal digits with an optional `+` sign.
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let digits = string.strip_prefix('+').unwrap_or(string);
+        if digits.is_empty() || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Err(NumberError::Invalid(string.to_owned()));
+        }
+        let ten = Self::from(10u64);
+        digits.bytes().try_fold(Self::ZERO, |value, digit| {
+            value
+                .checked_mul(ten)
+                .and_then(|value| value.checked_add(Self::from((digit - b'0') as u64)))
+                .ok_or(NumberError::Overflow)
+        })
+    }
+}
+
+impl Serialize for U256 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        numeric::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for U256 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        numeric::deserialize(deserializer)
+    }
+}
+
+/// Signed 256-bit integer in two's complement.
+///
+/// Borsh encodes it as 32 little-endian bytes.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, BorshSerialize, BorshDeserialize)]
+pub struct I256(U256);
+
+impl I256 {
+    pub const ZERO: Self = Self(U256::ZERO);
+    pub const ONE: Self = Self(U256::ONE);
+    pub const MAX: Self = Self(U256([u64::MAX, u64::MAX, u64::MAX, i64::MAX as u64]));
+    pub const MIN: Self = Self(U256([0, 0, 0, 1 << 63]));
+
+    pub const fn from_i128(value: i128) -> Self {
+        let fill = if value < 0 { u64::MAX } else { 0 };
+        Self(U256([value as u64, (value >> 64) as u64, fill, fill]))
+    }
+
+    /// Value as `i128`, or `None` if it does not fit.
+    pub fn to_i128(self) -> Option<i128> {
+        let U256([low, high, ..]) = self.0;
+        let value = (((high as u128) << 64) | low as u128) as i128;
+        (Self::from_i128(value) == self).then_some(value)
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.0.bit(255)
+    }
+
+    /// Absolute value, which always fits in `U256`.
+    pub fn unsigned_abs(self) -> U256 {
+        if self.is_negative() {
+            self.wrapping_neg().0
+        } else {
+            self.0
+        }
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        let sum = Self(self.0.overflowing_add(other.0).0);
+        let overflow =
+            self.is_negative() == other.is_negative() && sum.is_negative() != self.is_negative();
+        (!overflow).then_some(sum)
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        let difference = Self(self.0.overflowing_sub(other.0).0);
+        let overflow = self.is_negative() != other.is_negative()
+            && difference.is_negative() != self.is_negative();
+        (!overflow).then_some(difference)
+    }
+
+    pub fn checked_mul(self, other: Self) -> Option<Self> {
+        let magnitude = self.unsigned_abs().checked_mul(other.unsigned_abs())?;
+        Self::from_sign_magnitude(self.is_negative() != other.is_negative(), magnitude)
+    }
+
+    pub fn checked_div(self, other: Self) -> Option<Self> {
+        let magnitude = self.unsigned_abs().checked_div(other.unsigned_abs())?;
+        Self::from_sign_magnitude(self.is_negative() != other.is_negative(), magnitude)
+    }
+
+    /// Remainder with the sign of `self`, like `i128::checked_rem`.
+    pub fn checked_rem(self, other: Self) -> Option<Self> {
+        let magnitude = self.unsigned_abs().checked_rem(other.unsigned_abs())?;
+        Self::from_sign_magnitude(self.is_negative(), magnitude)
+    }
+
+    pub fn checked_neg(self) -> Option<Self> {
+        Self::from_sign_magnitude(!self.is_negative(), self.unsigned_abs())
+    }
+
+    fn wrapping_neg(self) -> Self {
+        let U256(limbs) = self.0;
+        Self(U256(limbs.map(|limb| !limb)).overflowing_add(U256::ONE).0)
+    }
+
+    fn from_sign_magnitude(negative: bool, magnitude: U256) -> Option<Self> {
+        let limit = Self::MIN.0;
+        match negative {
+            true if magnitude <= limit => Some(Self(magnitude).wrapping_neg()),
+            false if magnitude < limit => Some(Self(magnitude)),
+            _ => None,
+        }
+    }
+}
+
+impl From<i64> for I256 {
+    fn from(value: i64) -> Self {
+        Self::from_i128(value.into())
+    }
+}
+
+impl From<i128> for I256 {
+    fn from(value: i128) -> Self {
+        Self::from_i128(value)
+    }
+}
+
+impl Ord for I256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let biased = |value: &Self| {
+            let U256(mut limbs) = value.0;
+            limbs[3] ^= 1 << 63;
+            U256(limbs)
+        };
+        biased(self).cmp(&biased(other))
+    }
+}
+
+impl PartialOrd for I256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for I256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.is_negative() { "-" } else { "" };
+        write!(f, "{}{}", sign, self.unsigned_abs())
+    }
+}
+
+impl fmt::Debug for I256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "I256({})", self)
+    }
+}
+
+impl FromStr for I256 {
+    type Err = NumberError;
+
+    /// Parses decimal digits with an optional sign.
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let (negative, digits) = match string.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, string.strip_prefix('+').unwrap_or(string)),
+        };
+        if digits.starts_with('+') {
+            return Err(NumberError::Invalid(string.to_owned()));
+        }
+        let magnitude = digits.parse::<U256>().map_err(|error| match error {
+            NumberError::Invalid(_) => NumberError::Invalid(string.to_owned()),
+            error => error,
+        })?;
+        Self::from_sign_magnitude(negative, magnitude).ok_or(NumberError::Overflow)
+    }
+}
+
+impl Serialize for I256 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        numeric::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for I256 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        numeric::deserialize(deserializer)
+    }
+}
+
+#[cfg(feature = "schema")]
+mod schema {
+    use std::borrow::Cow;
+
+    use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+
+    use super::{I256, U256};
+
+    impl JsonSchema for U256 {
+        fn schema_name() -> Cow<'static, str> {
+            "U256".into()
+        }
+
+        fn json_schema(_: &mut SchemaGenerator) -> Schema {
+            json_schema!({ "type": "string", "pattern": "^\\+?[0-9]+$" })
+        }
+    }
+
+    impl JsonSchema for I256 {
+        fn schema_name() -> Cow<'static, str> {
+            "I256".into()
+        }
+
+        fn json_schema(_: &mut SchemaGenerator) -> Schema {
+            json_schema!({ "type": "string", "pattern": "^[-+]?[0-9]+$" })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const U256_MAX: &str =
+        "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+    const I256_MIN: &str =
+        "-57896044618658097711785492504343953926634992332820282019728792003956564819968";
+
+    fn u256(string: &str) -> U256 {
+        string.parse().unwrap()
+    }
+
+    fn i256(string: &str) -> I256 {
+        string.parse().unwrap()
+    }
+
+    #[test]
+    fn test_u256() {
+        assert_eq!(U256::MAX.to_string(), U256_MAX);
+        assert_eq!(u256(U256_MAX), U256::MAX);
+        assert_eq!(u256("0").to_string(), "0");
+        assert_eq!(
+            u256("10000000000000000000").to_string(),
+            "10000000000000000000"
+        );
+        assert_eq!("1".repeat(80).parse::<U256>(), Err(NumberError::Overflow));
+        assert!(matches!("-1".parse::<U256>(), Err(NumberError::Invalid(_))));
+
+        let big = u256("340282366920938463463374607431768211456"); // 2^128
+        assert_eq!(big.checked_mul(big), None);
+        assert_eq!(big.checked_add(U256::ONE).unwrap().to_u128(), None);
+        assert_eq!(
+            big.checked_mul(u256("1000")).unwrap().to_string(),
+            "340282366920938463463374607431768211456000"
+        );
+        assert_eq!(U256::MAX.checked_div(big), Some(U256::from(u128::MAX)));
+        assert_eq!(U256::MAX.checked_rem(big), Some(U256::from(u128::MAX)));
+        assert_eq!(U256::MAX.checked_div(U256::MAX), Some(U256::ONE));
+        assert_eq!(U256::MAX.checked_add(U256::ONE), None);
+        assert_eq!(U256::ZERO.checked_sub(U256::ONE), None);
+        assert_eq!(U256::ONE.checked_div(U256::ZERO), None);
+        assert!(big < U256::MAX && U256::from(u128::MAX) < big);
+    }
+
+    #[test]
+    fn test_i256() {
+        assert_eq!(I256::MIN.to_string(), I256_MIN);
+        assert_eq!(i256(I256_MIN), I256::MIN);
+        assert_eq!(I256::MAX.checked_add(I256::ONE), None);
+        assert_eq!(I256::MIN.checked_sub(I256::ONE), None);
+        assert_eq!(I256::MIN.checked_neg(), None);
+        assert_eq!(I256::MIN.checked_div(I256::from(-1i64)), None);
+        assert_eq!(i256("-7").checked_rem(i256("2")), Some(I256::from(-1i64)));
+        assert_eq!(i256("-3").checked_mul(i256("4")), Some(I256::from(-12i64)));
+        assert_eq!(
+            i256("-5").checked_add(i256("3")).unwrap().to_i128(),
+            Some(-2)
+        );
+        assert_eq!(I256::MAX.to_i128(), None);
+        assert!(I256::MIN < i256("-1") && i256("-1") < I256::ZERO && I256::ZERO < I256::MAX);
+        assert!(matches!(
+            "--1".parse::<I256>(),
+            Err(NumberError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_serde() {
+        assert_eq!(json!([U256::MAX, i256("-42")]), json!([U256_MAX, "-42"]));
+        let (unsigned, signed): (U256, I256) = serde_json::from_value(json!([12, "-42"])).unwrap();
+        assert_eq!((unsigned, signed), (U256::from(12u64), I256::from(-42i64)));
+
+        let bytes = borsh::to_vec(&I256::from(-1i64)).unwrap();
+        assert_eq!(bytes, [0xff; 32]);
+        assert_eq!(I256::try_from_slice(&bytes).unwrap(), I256::from(-1i64));
+    }
+}
@@ -0,0 +1,366 @@
+// Copyright 2025 Kore Ledger
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Byte arrays in states and events.
+//!
+//! Byte arrays are the object `{ "$bytes": "<base64>" }`, both as a `serde_json::Value`, e.g.
+//! for schemas, migrations and patches, and in the wire format. With
+//! `ExecutionOptions::dedicated_tags`, a final state encoded straight from `State` has them as
+//! raw bytes with the bytes tag instead, which hosts with an older SDK can not decode. Every
+//! decoder of the SDK reads that tag, as the `{ "$bytes": "<base64>" }` object in a `Value`.
+//!
+//! The `$bytes` key is reserved for byte arrays: an object with only that key and a base64
+//! string is read as a byte array by this module. The wire format never reinterprets other
+//! objects with the key, they are encoded as any object.
+//!
+//! Use `Bytes`, or this module with `#[serde(with = "kore_contract_sdk::bytes")]` on
+//! `Vec<u8>` and `[u8; N]` fields, like `serde_bytes`. Byte arrays stored before as arrays of
+//! numbers or base64 strings are still accepted. Types that use `serialize_bytes` directly,
+//! such as those of `serde_bytes`, are encoded as arrays of numbers, like `serde_json` does.
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct State {
+//!     #[serde(with = "kore_contract_sdk::bytes")]
+//!     hash: [u8; 32],
+//!     document: Bytes,
+//! }
+//! ```
+
+use std::{
+    fmt,
+    ops::{Deref, DerefMut},
+};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{self, MapAccess, SeqAccess, Visitor},
+    ser::SerializeMap,
+};
+use serde_json::{Map, Value};
+
+/// Key of the base64 encoded bytes in the `Value` representation.
+pub const BYTES_KEY: &str = "$bytes";
+
+/// Newtype name used by the wire format (de)serializers to recognize byte arrays.
+pub(crate) const TOKEN: &str = "$kore_contract_sdk::private::Bytes";
+
+/// Serializes a byte array, encoded as described in the module documentation.
+pub fn serialize<T, S>(bytes: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: AsRef<[u8]> + ?Sized,
+    S: Serializer,
+{
+    serializer.serialize_newtype_struct(TOKEN, &Raw(bytes.as_ref()))
+}
+
+/// Deserializes a byte array from raw bytes, `{ "$bytes": "<base64>" }`, a base64 string or
+/// an array of numbers.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: TryFrom<Vec<u8>>,
+    D: Deserializer<'de>,
+{
+    let bytes = deserializer.deserialize_newtype_struct(TOKEN, BytesVisitor)?;
+    let len = bytes.len();
+    T::try_from(bytes)
+        .map_err(|_| de::Error::invalid_length(len, &"a byte array of the expected length"))
+}
+
+/// Byte array, encoded as described in the module documentation.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash, BorshSerialize, BorshDeserialize,
+)]
+pub struct Bytes(pub Vec<u8>);
+
+impl Bytes {
+    pub fn into_vec(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl From<Vec<u8>> for Bytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<&[u8]> for Bytes {
+    fn from(bytes: &[u8]) -> Self {
+        Self(bytes.to_vec())
+    }
+}
+
+impl From<Bytes> for Vec<u8> {
+    fn from(bytes: Bytes) -> Self {
+        bytes.0
+    }
+}
+
+impl Deref for Bytes {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Vec<u8> {
+        &self.0
+    }
+}
+
+impl DerefMut for Bytes {
+    fn deref_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.0
+    }
+}
+
+impl AsRef<[u8]> for Bytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Serialize for Bytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(&self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Bytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize(deserializer)
+    }
+}
+
+#[cfg(feature = "schema")]
+impl schemars::JsonSchema for Bytes {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "Bytes".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "object",
+            "properties": { BYTES_KEY: { "type": "string", "contentEncoding": "base64" } },
+            "required": [BYTES_KEY],
+            "additionalProperties": false
+        })
+    }
+}
+
+/// Bytes as serialized inside the `TOKEN` newtype: raw bytes for binary formats and the wire
+/// format with dedicated tags, `{ "$bytes": "<base64>" }` for human readable formats such as
+/// `serde_json`.
+struct Raw<'a>(&'a [u8]);
+
+impl Serialize for Raw<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            let mut map = serializer.serialize_map(Some(1))?;
+            map.serialize_entry(BYTES_KEY, &encode(self.0))?;
+            map.end()
+        } else {
+            serializer.serialize_bytes(self.0)
+        }
+    }
+}
+
+struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a byte array")
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Vec<u8>, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(self)
+        } else {
+            deserializer.deserialize_byte_buf(self)
+        }
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Vec<u8>, E> {
+        Ok(bytes.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, bytes: Vec<u8>) -> Result<Vec<u8>, E> {
+        Ok(bytes)
+    }
+
+    fn visit_str<E: de::Error>(self, string: &str) -> Result<Vec<u8>, E> {
+        decode(string)
+            .ok_or_else(|| de::Error::invalid_value(de::Unexpected::Str(string), &"base64"))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        Ok(bytes)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Vec<u8>, A::Error> {
+        match map.next_entry::<String, String>()? {
+            Some((key, string)) if key == BYTES_KEY && map.next_key::<String>()?.is_none() => {
+                self.visit_str(&string)
+            }
+            _ => Err(de::Error::invalid_type(de::Unexpected::Map, &self)),
+        }
+    }
+}
+
+/// `{ "$bytes": "<base64>" }` for `bytes`.
+pub(crate) fn to_value(bytes: &[u8]) -> Value {
+    let mut map = Map::new();
+    map.insert(BYTES_KEY.to_owned(), Value::String(encode(bytes)));
+    Value::Object(map)
+}
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Standard base64 with padding.
+pub(crate) fn encode(bytes: &[u8]) -> String {
+    let mut string = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, byte)| {
+            group | (*byte as u32) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                string.push(ALPHABET[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                string.push('=');
+            }
+        }
+    }
+    string
+}
+
+/// Decodes standard base64 with padding. Only the canonical encoding is accepted, so that
+/// decoding and encoding again gives the same string.
+pub(crate) fn decode(string: &str) -> Option<Vec<u8>> {
+    let input = string.as_bytes();
+    if input.len() % 4 != 0 {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(input.len() / 4 * 3);
+    for (index, chunk) in input.chunks(4).enumerate() {
+        let last = index == input.len() / 4 - 1;
+        let padding = chunk.iter().rev().take_while(|byte| **byte == b'=').count();
+        if padding > 2 || (padding > 0 && !last) {
+            return None;
+        }
+        let mut group = 0u32;
+        for (i, byte) in chunk[..4 - padding].iter().enumerate() {
+            let value = ALPHABET.iter().position(|symbol| symbol == byte)? as u32;
+            group |= value << (18 - 6 * i);
+        }
+        let len = 3 - padding;
+        // Bits not covered by the decoded bytes must be zero.
+        if group & ((1 << (8 * (3 - len))) - 1) != 0 {
+            return None;
+        }
+        bytes.extend((0..len).map(|i| (group >> (16 - 8 * i)) as u8));
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ValueWrapper,
+        de::from_bytes,
+        ser::{Serializer, to_bytes},
+    };
+    use serde_json::json;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct State {
+        #[serde(with = "crate::bytes")]
+        hash: [u8; 4],
+        document: Bytes,
+        #[serde(with = "crate::bytes")]
+        legacy: Vec<u8>,
+    }
+
+    #[test]
+    fn test_base64() {
+        for (bytes, string) in [
+            (&b""[..], ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"\xff\xfe\x00\x7f", "//4Afw=="),
+        ] {
+            assert_eq!(encode(bytes), string);
+            assert_eq!(decode(string).as_deref(), Some(bytes));
+        }
+        for invalid in [
+            "Zg", "Zg=", "Zh==", "Z===", "Zg==Zg==", "Zm9v!A==", "Zm8=Zm8=",
+        ] {
+            assert_eq!(decode(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_bytes_encoding() {
+        let state = State {
+            hash: [1, 2, 3, 4],
+            document: Bytes(b"kore".to_vec()),
+            legacy: vec![255],
+        };
+        let value = serde_json::to_value(&state).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "hash": { "$bytes": "AQIDBA==" },
+                "document": { "$bytes": "a29yZQ==" },
+                "legacy": { "$bytes": "/w==" }
+            })
+        );
+        assert_eq!(
+            serde_json::from_value::<State>(value.clone()).unwrap(),
+            state
+        );
+
+        // The wire format has the same objects, both through the `Value` and directly.
+        let bytes = to_bytes(&state).unwrap();
+        assert_eq!(bytes, borsh::to_vec(&ValueWrapper(value.clone())).unwrap());
+        assert_eq!(from_bytes::<State>(&bytes).unwrap(), state);
+
+        // Dedicated tags keep the raw bytes, which both decoders read.
+        let mut serializer = Serializer::new().with_dedicated_tags(true);
+        state.serialize(&mut serializer).unwrap();
+        let tagged = serializer.into_bytes();
+        let document = [6, 4, 0, 0, 0, b'k', b'o', b'r', b'e'];
+        assert!(
+            tagged
+                .windows(document.len())
+                .any(|window| window == document)
+        );
+        assert_eq!(from_bytes::<State>(&tagged).unwrap(), state);
+        let decoded: ValueWrapper = borsh::from_slice(&tagged).unwrap();
+        assert_eq!(decoded.0, value);
+
+        // Other objects with the reserved key are not reinterpreted.
+        let object = json!({ "$bytes": "a29yZQ==" });
+        let encoded = borsh::to_vec(&ValueWrapper(object.clone())).unwrap();
+        assert_eq!(encoded[0], 4);
+        assert_eq!(to_bytes(&object).unwrap(), encoded);
+
+        // States stored before the bytes tag.
+        let old = json!({ "hash": [1, 2, 3, 4], "document": "a29yZQ==", "legacy": [255] });
+        assert_eq!(serde_json::from_value::<State>(old.clone()).unwrap(), state);
+        let old = borsh::to_vec(&ValueWrapper(old)).unwrap();
+        assert_eq!(from_bytes::<State>(&old).unwrap(), state);
+
+        let short = json!({ "hash": [1], "document": [], "legacy": [] });
+        assert!(serde_json::from_value::<State>(short).is_err());
+    }
+}
@@ -0,0 +1,452 @@
+// Copyright 2025 Kore Ledger
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Conversion between the `ValueWrapper` wire format and CBOR (RFC 8949).
+//!
+//! `from_wire` gives the CBOR of a state or event, and `to_wire` the wire format of a CBOR
+//! value, without going through `serde_json::Value`. A wire value converted to CBOR and back
+//! keeps its value:
+//!
+//! - Booleans, `null`, strings, arrays and byte arrays map to their CBOR counterparts, and
+//!   objects to maps with text keys in ascending order.
+//! - Integers map to CBOR integers, and floats to CBOR floats of the shortest width that
+//!   keeps their value.
+//! - Exact number literals map to integers, bignums (tags 2 and 3) or decimal fractions
+//!   (tag 4). Their value is kept, their spelling only when it has no exponent: `1.50` comes
+//!   back as `1.50` but `12.5e3` as `125e2`.
+//!
+//! CBOR values without a wire format counterpart are converted with loss:
+//!
+//! - Integer map keys become strings, as in JSON. Maps with other non-text keys or with a
+//!   repeated key are rejected.
+//! - Non-finite floats and `undefined` become `null`.
+//! - Numbers are given the wire encoding that `ValueWrapper` would give them, e.g. a bignum
+//!   that fits in 64 bits becomes a plain integer.
+//! - Tags other than 2, 3 and 4 are dropped and their content kept, e.g. a date time string
+//!   (tag 0) becomes a plain string. Bigfloats (tag 5) are rejected.
+
+use ciborium::{Value, value::Integer};
+
+use crate::{de::node::Node, error::ConvertError};
+
+const FORMAT: &str = "CBOR";
+
+const TAG_POSITIVE_BIGNUM: u64 = 2;
+const TAG_NEGATIVE_BIGNUM: u64 = 3;
+const TAG_DECIMAL_FRACTION: u64 = 4;
+const TAG_BIGFLOAT: u64 = 5;
+
+/// Converts a value in the wire format into CBOR.
+pub fn from_wire(bytes: &[u8]) -> Result<Vec<u8>, ConvertError> {
+    let value = to_cbor(Node::from_bytes(bytes)?)?;
+    let mut output = Vec::new();
+    ciborium::into_writer(&value, &mut output).map_err(format_error)?;
+    Ok(output)
+}
+
+/// Converts a CBOR value into the wire format.
+pub fn to_wire(cbor: &[u8]) -> Result<Vec<u8>, ConvertError> {
+    let mut reader = cbor;
+    let value: Value = ciborium::from_reader(&mut reader).map_err(format_error)?;
+    if !reader.is_empty() {
+        return Err(format_error("Trailing bytes after the value"));
+    }
+    Ok(from_cbor(value)?.to_bytes())
+}
+
+fn format_error<E: ToString>(error: E) -> ConvertError {
+    ConvertError::Format {
+        format: FORMAT,
+        message: error.to_string(),
+    }
+}
+
+fn to_cbor(node: Node) -> Result<Value, ConvertError> {
+    Ok(match node {
+        Node::Bool(value) => Value::Bool(value),
+        Node::F64(value) => Value::Float(value),
+        Node::I64(value) => Value::Integer(value.into()),
+        Node::U64(value) => Value::Integer(value.into()),
+        Node::Literal(literal) => {
+            let Some(fraction) = Fraction::parse(&literal) else {
+                return Err(ConvertError::Unsupported(format!("number {}", literal)));
+            };
+            let mantissa = integer_to_cbor(fraction.negative, &fraction.digits);
+            if fraction.exponent == 0 {
+                mantissa
+            } else {
+                let exponent = Value::Integer(fraction.exponent.into());
+                Value::Tag(
+                    TAG_DECIMAL_FRACTION,
+                    Box::new(Value::Array(vec![exponent, mantissa])),
+                )
+            }
+        }
+        Node::String(value) => Value::Text(value),
+        Node::Array(elements) => Value::Array(
+            elements
+                .into_iter()
+                .map(to_cbor)
+                .collect::<Result<_, _>>()?,
+        ),
+        Node::Object(entries) => Value::Map(
+            entries
+                .into_iter()
+                .map(|(key, value)| Ok((Value::Text(key), to_cbor(value)?)))
+                .collect::<Result<_, ConvertError>>()?,
+        ),
+        Node::Null => Value::Null,
+        Node::Bytes(bytes) => Value::Bytes(bytes),
+    })
+}
+
+/// CBOR integer, or bignum if it does not fit.
+fn integer_to_cbor(negative: bool, digits: &str) -> Value {
+    let sign = if negative { "-" } else { "" };
+    let integer = format!("{}{}", sign, digits)
+        .parse::<i128>()
+        .ok()
+        .and_then(|integer| Integer::try_from(integer).ok());
+    if let Some(integer) = integer {
+        return Value::Integer(integer);
+    }
+    let magnitude = digits_to_bytes(digits);
+    if negative {
+        // A negative bignum encodes `-1 - n`.
+        Value::Tag(
+            TAG_NEGATIVE_BIGNUM,
+            Box::new(Value::Bytes(decrement(magnitude))),
+        )
+    } else {
+        Value::Tag(TAG_POSITIVE_BIGNUM, Box::new(Value::Bytes(magnitude)))
+    }
+}
+
+fn from_cbor(value: Value) -> Result<Node, ConvertError> {
+    Ok(match value {
+        Value::Integer(integer) => Node::number(i128::from(integer).to_string()),
+        Value::Bytes(bytes) => Node::Bytes(bytes),
+        Value::Float(float) => Node::float(float),
+        Value::Text(text) => Node::String(text),
+        Value::Bool(value) => Node::Bool(value),
+        Value::Null => Node::Null,
+        Value::Tag(TAG_POSITIVE_BIGNUM | TAG_NEGATIVE_BIGNUM, _) => {
+            Node::number(integer_literal(value)?)
+        }
+        Value::Tag(TAG_DECIMAL_FRACTION, content) => {
+            let Value::Array(parts) = *content else {
+                return Err(format_error("Invalid decimal fraction"));
+            };
+            let Ok([exponent, mantissa]) = <[Value; 2]>::try_from(parts) else {
+                return Err(format_error("Invalid decimal fraction"));
+            };
+            let exponent = exponent
+                .as_integer()
+                .and_then(|exponent| i64::try_from(exponent).ok())
+                .ok_or_else(|| format_error("Invalid decimal fraction exponent"))?;
+            let mantissa = integer_literal(mantissa)?;
+            let (negative, digits) = match mantissa.strip_prefix('-') {
+                Some(digits) => (true, digits.to_owned()),
+                None => (false, mantissa),
+            };
+            let fraction = Fraction {
+                negative,
+                digits,
+                exponent,
+            };
+            Node::number(fraction.to_literal())
+        }
+        Value::Tag(TAG_BIGFLOAT, _) => {
+            return Err(ConvertError::Unsupported("CBOR bigfloat".to_owned()));
+        }
+        Value::Tag(_, content) => from_cbor(*content)?,
+        Value::Array(elements) => Node::Array(
+            elements
+                .into_iter()
+                .map(from_cbor)
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Map(entries) => Node::object(
+            entries
+                .into_iter()
+                .map(|(key, value)| Ok((key_from_cbor(key)?, from_cbor(value)?)))
+                .collect::<Result<_, ConvertError>>()?,
+        )?,
+        _ => return Err(ConvertError::Unsupported(format!("CBOR value {:?}", value))),
+    })
+}
+
+fn key_from_cbor(key: Value) -> Result<String, ConvertError> {
+    match key {
+        Value::Text(key) => Ok(key),
+        Value::Integer(key) => Ok(i128::from(key).to_string()),
+        _ => Err(ConvertError::Unsupported(
+            "CBOR map key that is not a text or an integer".to_owned(),
+        )),
+    }
+}
+
+/// Decimal literal of a CBOR integer or bignum.
+fn integer_literal(value: Value) -> Result<String, ConvertError> {
+    match value {
+        Value::Integer(integer) => Ok(i128::from(integer).to_string()),
+        Value::Tag(tag, content) => match (tag, *content) {
+            (TAG_POSITIVE_BIGNUM, Value::Bytes(bytes)) => Ok(bytes_to_digits(&bytes)),
+            (TAG_NEGATIVE_BIGNUM, Value::Bytes(bytes)) => {
+                Ok(format!("-{}", bytes_to_digits(&increment(bytes))))
+            }
+            _ => Err(format_error("Invalid bignum")),
+        },
+        _ => Err(format_error("Expected an integer or a bignum")),
+    }
+}
+
+/// Number literal split into `digits * 10^exponent`.
+struct Fraction {
+    negative: bool,
+    /// Decimal digits without leading zeros.
+    digits: String,
+    exponent: i64,
+}
+
+impl Fraction {
+    /// Longest run of zeros written out instead of using an exponent.
+    const MAX_PADDING: usize = 20;
+
+    /// Splits a JSON number literal, or `None` if its exponent does not fit in an `i64`.
+    fn parse(literal: &str) -> Option<Self> {
+        let (negative, literal) = match literal.strip_prefix('-') {
+            Some(literal) => (true, literal),
+            None => (false, literal),
+        };
+        let (mantissa, exponent) = match literal.split_once(['e', 'E']) {
+            Some((mantissa, exponent)) => (mantissa, exponent.parse::<i64>().ok()?),
+            None => (literal, 0),
+        };
+        let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let exponent = exponent.checked_sub(i64::try_from(fraction.len()).ok()?)?;
+        let digits = format!("{}{}", integer, fraction);
+        let digits = match digits.trim_start_matches('0') {
+            "" => "0".to_owned(),
+            digits => digits.to_owned(),
+        };
+        Some(Self {
+            negative,
+            digits,
+            exponent,
+        })
+    }
+
+    /// Literal with a decimal point for negative exponents, unless that needs too many zeros.
+    fn to_literal(&self) -> String {
+        let sign = if self.negative { "-" } else { "" };
+        let scale = self.exponent.unsigned_abs() as usize;
+        if self.exponent == 0 {
+            format!("{}{}", sign, self.digits)
+        } else if self.exponent > 0 || scale > self.digits.len() + Self::MAX_PADDING {
+            format!("{}{}e{}", sign, self.digits, self.exponent)
+        } else {
+            let digits = format!("{:0>width$}", self.digits, width = scale + 1);
+            let (integer, fraction) = digits.split_at(digits.len() - scale);
+            format!("{}{}.{}", sign, integer, fraction)
+        }
+    }
+}
+
+/// Big endian bytes of a number given by its decimal digits.
+fn digits_to_bytes(digits: &str) -> Vec<u8> {
+    let mut bytes: Vec<u8> = Vec::new();
+    for digit in digits.bytes() {
+        let mut carry = u32::from(digit - b'0');
+        for byte in bytes.iter_mut().rev() {
+            let value = u32::from(*byte) * 10 + carry;
+            *byte = value as u8;
+            carry = value >> 8;
+        }
+        if carry > 0 {
+            bytes.insert(0, carry as u8);
+        }
+    }
+    bytes
+}
+
+/// Decimal digits of a number given by its big endian bytes.
+fn bytes_to_digits(bytes: &[u8]) -> String {
+    const BASE: u64 = 1_000_000_000;
+    // Little endian limbs of nine decimal digits.
+    let mut limbs: Vec<u64> = Vec::new();
+    for byte in bytes {
+        let mut carry = u64::from(*byte);
+        for limb in limbs.iter_mut() {
+            let value = *limb * 256 + carry;
+            *limb = value % BASE;
+            carry = value / BASE;
+        }
+        if carry > 0 {
+            limbs.push(carry);
+        }
+    }
+    let mut limbs = limbs.iter().rev();
+    let mut digits = limbs.next().map_or_else(|| "0".to_owned(), u64::to_string);
+    for limb in limbs {
+        digits.push_str(&format!("{:09}", limb));
+    }
+    digits
+}
+
+fn increment(mut bytes: Vec<u8>) -> Vec<u8> {
+    for byte in bytes.iter_mut().rev() {
+        let (value, overflow) = byte.overflowing_add(1);
+        *byte = value;
+        if !overflow {
+            return bytes;
+        }
+    }
+    bytes.insert(0, 1);
+    bytes
+}
+
+/// Subtracts one from a positive number, without leaving leading zeros.
+fn decrement(mut bytes: Vec<u8>) -> Vec<u8> {
+    for byte in bytes.iter_mut().rev() {
+        let (value, overflow) = byte.overflowing_sub(1);
+        *byte = value;
+        if !overflow {
+            break;
+        }
+    }
+    let start = bytes
+        .iter()
+        .position(|byte| *byte != 0)
+        .unwrap_or(bytes.len());
+    bytes.split_off(start)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{ValueWrapper, bytes::Bytes, ser::Serializer};
+
+    fn cbor(value: &Value) -> Vec<u8> {
+        let mut output = Vec::new();
+        ciborium::into_writer(value, &mut output).unwrap();
+        output
+    }
+
+    fn read(bytes: &[u8]) -> Value {
+        ciborium::from_reader(bytes).unwrap()
+    }
+
+    #[test]
+    fn test_cbor_round_trip() {
+        #[derive(serde::Serialize)]
+        struct State {
+            name: String,
+            count: u64,
+            balance: i64,
+            ratio: f64,
+            tags: Vec<Option<bool>>,
+            content: Bytes,
+        }
+
+        let state = State {
+            name: "Alice".to_owned(),
+            count: u64::MAX,
+            balance: -5,
+            ratio: 0.25,
+            tags: vec![Some(true), None],
+            content: Bytes(vec![1, 2]),
+        };
+        let mut serializer = Serializer::new().with_dedicated_tags(true);
+        serde::Serialize::serialize(&state, &mut serializer).unwrap();
+        let wire = serializer.into_bytes();
+        let encoded = from_wire(&wire).unwrap();
+        let Value::Map(entries) = read(&encoded) else {
+            panic!("Expected a map");
+        };
+        assert_eq!(entries[1].0, Value::Text("content".to_owned()));
+        assert_eq!(entries[1].1, Value::Bytes(vec![1, 2]));
+        assert_eq!(to_wire(&encoded).unwrap(), wire);
+    }
+
+    #[test]
+    fn test_cbor_number_literals() {
+        let literals = [
+            ("123456789012345678901234567890", Some(TAG_POSITIVE_BIGNUM)),
+            ("-123456789012345678901234567890", Some(TAG_NEGATIVE_BIGNUM)),
+            ("-18446744073709551616", None),
+            ("1.50", Some(TAG_DECIMAL_FRACTION)),
+            ("-0.0000000000000000000010", Some(TAG_DECIMAL_FRACTION)),
+            ("15e300", Some(TAG_DECIMAL_FRACTION)),
+            ("1e-400", Some(TAG_DECIMAL_FRACTION)),
+        ];
+        for (literal, tag) in literals {
+            let wire = Node::Literal(literal.to_owned()).to_bytes();
+            let encoded = from_wire(&wire).unwrap();
+            let found = match read(&encoded) {
+                Value::Tag(tag, _) => Some(tag),
+                _ => None,
+            };
+            assert_eq!(found, tag, "{}", literal);
+            assert_eq!(to_wire(&encoded).unwrap(), wire, "{}", literal);
+        }
+
+        let spelling = Node::Literal("12.5e3".to_owned()).to_bytes();
+        let wire = to_wire(&from_wire(&spelling).unwrap()).unwrap();
+        assert_eq!(
+            Node::from_bytes(&wire).unwrap(),
+            Node::Literal("125e2".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_cbor_lossy() {
+        let value = Value::Map(vec![
+            (Value::Integer(2.into()), Value::Float(f64::NAN)),
+            (
+                Value::Text("1".to_owned()),
+                Value::Tag(0, Box::new(Value::Text("2025".into()))),
+            ),
+            (
+                Value::Text("big".to_owned()),
+                Value::Tag(2, Box::new(Value::Bytes(vec![1, 0]))),
+            ),
+            (
+                Value::Text("ratio".to_owned()),
+                Value::Tag(4, Box::new(Value::Array(vec![(-1).into(), 15.into()]))),
+            ),
+        ]);
+        let wire = to_wire(&cbor(&value)).unwrap();
+        let expected = json!({ "1": "2025", "2": null, "big": 256, "ratio": 1.5 });
+        assert_eq!(wire, borsh::to_vec(&ValueWrapper(expected)).unwrap());
+
+        let duplicate = Value::Map(vec![
+            (Value::Integer(1.into()), Value::Null),
+            (Value::Text("1".to_owned()), Value::Null),
+        ]);
+        assert_eq!(
+            to_wire(&cbor(&duplicate)),
+            Err(ConvertError::DuplicateKey("1".to_owned()))
+        );
+        let key = Value::Map(vec![(Value::Bool(true), Value::Null)]);
+        assert!(matches!(
+            to_wire(&cbor(&key)),
+            Err(ConvertError::Unsupported(_))
+        ));
+        let bigfloat = Value::Tag(5, Box::new(Value::Array(vec![1.into(), 3.into()])));
+        assert!(matches!(
+            to_wire(&cbor(&bigfloat)),
+            Err(ConvertError::Unsupported(_))
+        ));
+
+        let mut trailing = cbor(&Value::Null);
+        trailing.push(0);
+        assert!(matches!(
+            to_wire(&trailing),
+            Err(ConvertError::Format { .. })
+        ));
+        assert!(matches!(from_wire(&[9]), Err(ConvertError::Decode(_))));
+    }
+}
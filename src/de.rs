@@ -0,0 +1,655 @@
+// Copyright 2025 Kore Ledger
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Serde deserialization straight from the `ValueWrapper` wire format.
+//!
+//! Decoding a `ValueWrapper` and then converting it with `serde_json::from_value` builds the
+//! whole `Value` tree before the contract type. This deserializer reads the borsh bytes
+//! directly into the target type, borrowing strings from the input when the type allows it.
+//! It accepts the same types as the `Value` path, e.g. enums are strings or single entry
+//! objects, and rejects the same malformed input, such as duplicate object keys. Byte arrays
+//! are given to `crate::bytes` and `deserialize_bytes` as borrowed bytes, and to any other
+//! type as their `{ "$bytes": "<base64>" }` value. The numbers of the SDK number types are
+//! given as their string, as in the `Value`. Number literals are decoded by
+//! `value_wrapper::number_literal` in both paths, so they reach the target type as the same
+//! number.
+
+use std::collections::BTreeSet;
+
+use serde::de::{self, DeserializeSeed, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor};
+
+use crate::{
+    bytes,
+    error::DecodeError,
+    value_wrapper::{number_literal, numeric_literal},
+};
+
+#[cfg(any(feature = "cbor", feature = "msgpack"))]
+pub(crate) mod node;
+
+pub(crate) mod tag {
+    pub const BOOL: u8 = 0;
+    pub const NUMBER: u8 = 1;
+    pub const STRING: u8 = 2;
+    pub const ARRAY: u8 = 3;
+    pub const OBJECT: u8 = 4;
+    pub const NULL: u8 = 5;
+    pub const BYTES: u8 = 6;
+    pub const NUMERIC: u8 = 7;
+
+    pub const F64: u8 = 0;
+    pub const I64: u8 = 1;
+    pub const U64: u8 = 2;
+    pub const LITERAL: u8 = 3;
+}
+
+/// Limits applied when decoding the wire format, so that hostile input can not exhaust the
+/// stack or the memory of the contract.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeLimits {
+    /// Maximum nesting of arrays, objects and enum variants with content.
+    pub max_depth: usize,
+    /// Maximum number of elements of an array or entries of an object.
+    pub max_elements: usize,
+    /// Maximum length in bytes of a string, byte array, object key or number literal.
+    pub max_string_len: usize,
+    /// Maximum size in bytes of the encoded value.
+    pub max_size: usize,
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 128,
+            max_elements: 1 << 20,
+            max_string_len: 16 << 20,
+            max_size: 64 << 20,
+        }
+    }
+}
+
+/// Deserializes a `T` from its `ValueWrapper` borsh encoding with the default limits.
+pub fn from_bytes<'de, T: de::Deserialize<'de>>(bytes: &'de [u8]) -> Result<T, DecodeError> {
+    from_bytes_with_limits(bytes, DecodeLimits::default())
+}
+
+/// Deserializes a `T` from its `ValueWrapper` borsh encoding.
+pub fn from_bytes_with_limits<'de, T: de::Deserialize<'de>>(
+    bytes: &'de [u8],
+    limits: DecodeLimits,
+) -> Result<T, DecodeError> {
+    let mut deserializer = Deserializer::with_limits(bytes, limits);
+    let value = T::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+/// Encoded elements of an array, split without decoding them.
+pub(crate) fn array_elements(
+    bytes: &[u8],
+    limits: DecodeLimits,
+) -> Result<Vec<&[u8]>, DecodeError> {
+    let mut deserializer = Deserializer::with_limits(bytes, limits);
+    match deserializer.read_u8()? {
+        tag::ARRAY => {}
+        tag => {
+            return Err(DecodeError::Message(format!(
+                "Expected an array, found value tag {}",
+                tag
+            )));
+        }
+    }
+    let len = deserializer.read_len()?;
+    deserializer.enter()?;
+    let mut elements = Vec::with_capacity(len.min(4096));
+    for _ in 0..len {
+        let element = deserializer.input;
+        de::Deserializer::deserialize_ignored_any(&mut deserializer, de::IgnoredAny)?;
+        elements.push(&element[..element.len() - deserializer.input.len()]);
+    }
+    deserializer.end()?;
+    Ok(elements)
+}
+
+/// Deserializer over the `ValueWrapper` borsh encoding.
+pub struct Deserializer<'de> {
+    input: &'de [u8],
+    limits: DecodeLimits,
+    consumed: usize,
+    depth: usize,
+}
+
+impl<'de> Deserializer<'de> {
+    pub fn from_bytes(input: &'de [u8]) -> Self {
+        Self::with_limits(input, DecodeLimits::default())
+    }
+
+    pub fn with_limits(input: &'de [u8], limits: DecodeLimits) -> Self {
+        Self {
+            input,
+            limits,
+            consumed: 0,
+            depth: 0,
+        }
+    }
+
+    /// Fails if there are bytes left after the decoded value.
+    pub fn end(&self) -> Result<(), DecodeError> {
+        if self.input.is_empty() {
+            Ok(())
+        } else {
+            Err(DecodeError::TrailingBytes)
+        }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'de [u8], DecodeError> {
+        self.consumed = self.consumed.saturating_add(len);
+        if self.consumed > self.limits.max_size {
+            return Err(DecodeError::SizeLimit(self.limits.max_size));
+        }
+        if self.input.len() < len {
+            return Err(DecodeError::UnexpectedEnd);
+        }
+        let (bytes, rest) = self.input.split_at(len);
+        self.input = rest;
+        Ok(bytes)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn peek(&self) -> Result<u8, DecodeError> {
+        self.input
+            .first()
+            .copied()
+            .ok_or(DecodeError::UnexpectedEnd)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn read_u32(&mut self) -> Result<usize, DecodeError> {
+        Ok(u32::from_le_bytes(self.take_array()?) as usize)
+    }
+
+    /// Length of an array or object.
+    fn read_len(&mut self) -> Result<usize, DecodeError> {
+        let len = self.read_u32()?;
+        if len > self.limits.max_elements {
+            return Err(DecodeError::ElementLimit(self.limits.max_elements));
+        }
+        Ok(len)
+    }
+
+    fn read_bytes(&mut self) -> Result<&'de [u8], DecodeError> {
+        let len = self.read_u32()?;
+        if len > self.limits.max_string_len {
+            return Err(DecodeError::StringLimit(self.limits.max_string_len));
+        }
+        self.take(len)
+    }
+
+    fn read_str(&mut self) -> Result<&'de str, DecodeError> {
+        std::str::from_utf8(self.read_bytes()?).map_err(|_| DecodeError::InvalidUtf8)
+    }
+
+    /// Enters a nested array, object or enum variant.
+    fn enter(&mut self) -> Result<(), DecodeError> {
+        if self.depth >= self.limits.max_depth {
+            return Err(DecodeError::DepthLimit(self.limits.max_depth));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn deserialize_number<V: Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value, DecodeError> {
+        match self.read_u8()? {
+            tag::F64 => {
+                let float = f64::from_le_bytes(self.take_array()?);
+                if !float.is_finite() {
+                    return Err(DecodeError::InvalidNumber(float.to_string()));
+                }
+                visitor.visit_f64(float)
+            }
+            tag::I64 => visitor.visit_i64(i64::from_le_bytes(self.take_array()?)),
+            tag::U64 => visitor.visit_u64(u64::from_le_bytes(self.take_array()?)),
+            tag::LITERAL => {
+                // Visited by the number itself, as `serde_json::from_value` does with the
+                // number of the `Value` path.
+                let number = number_literal(self.read_str()?)?;
+                de::Deserializer::deserialize_any(number, visitor)
+                    .map_err(|error| DecodeError::Message(error.to_string()))
+            }
+            tag => Err(DecodeError::InvalidNumberTag(tag)),
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = DecodeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        match self.read_u8()? {
+            tag::BOOL => match self.read_u8()? {
+                0 => visitor.visit_bool(false),
+                1 => visitor.visit_bool(true),
+                byte => Err(DecodeError::InvalidBool(byte)),
+            },
+            tag::NUMBER => self.deserialize_number(visitor),
+            tag::STRING => visitor.visit_borrowed_str(self.read_str()?),
+            tag::ARRAY => {
+                let len = self.read_len()?;
+                self.enter()?;
+                let mut seq = Seq {
+                    de: &mut *self,
+                    remaining: len,
+                };
+                let value = visitor.visit_seq(&mut seq)?;
+                if seq.remaining != 0 {
+                    return Err(de::Error::invalid_length(len, &"fewer elements in array"));
+                }
+                self.depth -= 1;
+                Ok(value)
+            }
+            tag::OBJECT => {
+                let len = self.read_len()?;
+                self.enter()?;
+                let mut map = Map {
+                    de: &mut *self,
+                    remaining: len,
+                    keys: BTreeSet::new(),
+                };
+                let value = visitor.visit_map(&mut map)?;
+                if map.remaining != 0 {
+                    return Err(de::Error::invalid_length(len, &"fewer elements in map"));
+                }
+                self.depth -= 1;
+                Ok(value)
+            }
+            tag::NULL => visitor.visit_unit(),
+            tag::BYTES => {
+                let value = bytes::to_value(self.read_bytes()?);
+                de::Deserializer::deserialize_any(value, visitor)
+                    .map_err(|error| DecodeError::Message(error.to_string()))
+            }
+            tag::NUMERIC => visitor.visit_borrowed_str(numeric_literal(self.read_str()?)?),
+            tag => Err(DecodeError::InvalidTag(tag)),
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        if self.peek()? == tag::BYTES {
+            self.read_u8()?;
+            visitor.visit_borrowed_bytes(self.read_bytes()?)
+        } else {
+            self.deserialize_any(visitor)
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        if self.peek()? == tag::NULL {
+            self.read_u8()?;
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DecodeError> {
+        if name == bytes::TOKEN && self.peek()? == tag::BYTES {
+            return self.deserialize_bytes(visitor);
+        }
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DecodeError> {
+        match self.read_u8()? {
+            tag::STRING => {
+                visitor.visit_enum(de::value::BorrowedStrDeserializer::new(self.read_str()?))
+            }
+            tag::OBJECT => match self.read_len()? {
+                1 => {
+                    self.enter()?;
+                    let value = visitor.visit_enum(Enum { de: &mut *self })?;
+                    self.depth -= 1;
+                    Ok(value)
+                }
+                _ => Err(DecodeError::Message(
+                    "Expected an object with a single variant".to_owned(),
+                )),
+            },
+            tag => Err(DecodeError::Message(format!(
+                "Expected an enum, found value tag {}",
+                tag
+            ))),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        self.deserialize_any(de::IgnoredAny)?;
+        visitor.visit_unit()
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string unit unit_struct
+        seq tuple tuple_struct map struct identifier
+    }
+}
+
+struct Seq<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    remaining: usize,
+}
+
+impl<'de> SeqAccess<'de> for Seq<'_, 'de> {
+    type Error = DecodeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, DecodeError> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        // The length comes from the input, so it is not trusted for preallocation.
+        Some(self.remaining.min(4096))
+    }
+}
+
+struct Map<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    remaining: usize,
+    keys: BTreeSet<&'de str>,
+}
+
+impl<'de> MapAccess<'de> for Map<'_, 'de> {
+    type Error = DecodeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, DecodeError> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        let key = self.de.read_str()?;
+        if !self.keys.insert(key) {
+            return Err(DecodeError::DuplicateKey(key.to_owned()));
+        }
+        seed.deserialize(Key(key)).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, DecodeError> {
+        seed.deserialize(&mut *self.de)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining.min(4096))
+    }
+}
+
+/// Object key. Like serde_json, integer and bool keys are parsed from the string.
+struct Key<'de>(&'de str);
+
+macro_rules! deserialize_parsed_key {
+    ($($method:ident => $visit:ident),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+                match self.0.parse() {
+                    Ok(value) => visitor.$visit(value),
+                    Err(_) => visitor.visit_borrowed_str(self.0),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Key<'de> {
+    type Error = DecodeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    deserialize_parsed_key! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DecodeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DecodeError> {
+        visitor.visit_enum(de::value::BorrowedStrDeserializer::new(self.0))
+    }
+
+    serde::forward_to_deserialize_any! {
+        f32 f64 char str string bytes byte_buf unit unit_struct seq tuple tuple_struct map
+        struct identifier ignored_any
+    }
+}
+
+/// Enum encoded as an object with a single entry from the variant name to its content.
+struct Enum<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+}
+
+impl<'de> EnumAccess<'de> for Enum<'_, 'de> {
+    type Error = DecodeError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self), DecodeError> {
+        let variant = self.de.read_str()?;
+        let value = seed.deserialize(de::value::BorrowedStrDeserializer::new(variant))?;
+        Ok((value, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Enum<'_, 'de> {
+    type Error = DecodeError;
+
+    fn unit_variant(self) -> Result<(), DecodeError> {
+        de::Deserialize::deserialize(self.de)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, DecodeError> {
+        seed.deserialize(self.de)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DecodeError> {
+        de::Deserializer::deserialize_seq(self.de, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DecodeError> {
+        de::Deserializer::deserialize_map(self.de, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ValueWrapper;
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Event {
+        Reset,
+        Set(u32),
+        Move { x: i64, y: i64 },
+        Pair(String, Option<bool>),
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct State<'a> {
+        name: &'a str,
+        values: Vec<f64>,
+        events: Vec<Event>,
+        owner: Option<String>,
+        #[serde(default)]
+        missing: u8,
+    }
+
+    fn encode<T: Serialize>(value: &T) -> Vec<u8> {
+        borsh::to_vec(&ValueWrapper(serde_json::to_value(value).unwrap())).unwrap()
+    }
+
+    #[test]
+    fn test_from_bytes() {
+        let bytes = encode(&json!({
+            "name": "subject",
+            "values": [1.5, -2, 3],
+            "events": ["Reset", { "Set": 1 }, { "Move": { "x": -1, "y": 2 } }, { "Pair": ["a", null] }],
+            "owner": null,
+            "ignored": { "nested": [1, "two", null] }
+        }));
+        let state: State = from_bytes(&bytes).unwrap();
+        assert_eq!(
+            state,
+            State {
+                name: "subject",
+                values: vec![1.5, -2.0, 3.0],
+                events: vec![
+                    Event::Reset,
+                    Event::Set(1),
+                    Event::Move { x: -1, y: 2 },
+                    Event::Pair("a".to_owned(), None),
+                ],
+                owner: None,
+                missing: 0,
+            }
+        );
+        assert_eq!(
+            from_bytes::<Event>(&encode(&Event::Set(3))).unwrap(),
+            Event::Set(3)
+        );
+    }
+
+    #[test]
+    fn test_from_bytes_errors() {
+        let bytes = encode(&json!({ "name": 1 }));
+        assert!(matches!(
+            from_bytes::<State>(&bytes),
+            Err(DecodeError::Message(_))
+        ));
+        let bytes = encode(&json!(-1));
+        assert!(matches!(
+            from_bytes::<u32>(&bytes),
+            Err(DecodeError::Message(_))
+        ));
+
+        let mut bytes = encode(&json!(1));
+        bytes.push(0);
+        assert_eq!(from_bytes::<u32>(&bytes), Err(DecodeError::TrailingBytes));
+        assert_eq!(
+            from_bytes::<u32>(&[1, 1, 0]),
+            Err(DecodeError::UnexpectedEnd)
+        );
+        assert_eq!(from_bytes::<u32>(&[9]), Err(DecodeError::InvalidTag(9)));
+
+        let entry = [&[1, 0, 0, 0, b'a'][..], &encode(&json!(1))].concat();
+        let bytes = [&[4, 2, 0, 0, 0][..], &entry, &entry].concat();
+        assert_eq!(
+            from_bytes::<serde_json::Value>(&bytes),
+            Err(DecodeError::DuplicateKey("a".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_from_bytes_limits() {
+        let limits = DecodeLimits {
+            max_depth: 2,
+            max_elements: 3,
+            max_string_len: 4,
+            max_size: 32,
+        };
+        let nested = encode(&json!([[1]]));
+        assert_eq!(
+            from_bytes_with_limits::<Vec<Vec<u8>>>(&nested, limits),
+            Ok(vec![vec![1]])
+        );
+        let nested = encode(&json!({ "a": [[1]] }));
+        assert_eq!(
+            from_bytes_with_limits::<serde_json::Value>(&nested, limits),
+            Err(DecodeError::DepthLimit(2))
+        );
+        let bytes = encode(&json!([{ "Move": { "x": 1, "y": 2 } }]));
+        let result = from_bytes_with_limits::<Vec<Event>>(&bytes, limits);
+        assert_eq!(result, Err(DecodeError::DepthLimit(2)));
+        assert_eq!(
+            from_bytes_with_limits::<Vec<u8>>(&[3, 255, 255, 255, 255], limits),
+            Err(DecodeError::ElementLimit(3))
+        );
+        assert_eq!(
+            from_bytes_with_limits::<&str>(&encode(&json!("hello")), limits),
+            Err(DecodeError::StringLimit(4))
+        );
+        assert_eq!(
+            from_bytes_with_limits::<Vec<u8>>(&encode(&json!([1, 2, 3])), limits),
+            Err(DecodeError::SizeLimit(32))
+        );
+    }
+}
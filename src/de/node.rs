@@ -0,0 +1,196 @@
+// Copyright 2025 Kore Ledger
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Untyped tree of the wire format, for the conversions to other data formats.
+//!
+//! Unlike `serde_json::Value`, it keeps byte arrays and number literals apart from objects and
+//! strings, so they can be mapped to their counterparts in the other format.
+
+use std::collections::BTreeSet;
+
+use super::{Deserializer, tag};
+use crate::{
+    error::{ConvertError, DecodeError},
+    value_wrapper::{float_value, numeric_literal},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Node {
+    Bool(bool),
+    F64(f64),
+    I64(i64),
+    U64(u64),
+    /// Exact number literal, for numbers that do not fit in the other number types.
+    Literal(String),
+    String(String),
+    Array(Vec<Node>),
+    /// Entries in ascending key order.
+    Object(Vec<(String, Node)>),
+    Null,
+    Bytes(Vec<u8>),
+}
+
+impl Node {
+    /// Decodes a node from the wire format with the default `DecodeLimits`.
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut deserializer = Deserializer::from_bytes(bytes);
+        let node = deserializer.node()?;
+        deserializer.end()?;
+        Ok(node)
+    }
+
+    /// Encodes the node into the wire format, as `ValueWrapper` does.
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let mut output = Vec::new();
+        self.write(&mut output);
+        output
+    }
+
+    /// Number with the encoding that `ValueWrapper` gives to `literal`: an integer if it fits
+    /// in 64 bits, a float if the float has the same spelling, otherwise the literal.
+    pub(crate) fn number(literal: String) -> Self {
+        if let Ok(integer) = literal.parse::<i64>() {
+            return Self::I64(integer);
+        }
+        if let Ok(integer) = literal.parse::<u64>() {
+            return Self::U64(integer);
+        }
+        let exact = literal.parse().ok().and_then(|number| float_value(&number));
+        match exact {
+            Some(float) => Self::F64(float),
+            None => Self::Literal(literal),
+        }
+    }
+
+    /// Float number, or `null` if it is not finite, as in `serde_json`.
+    pub(crate) fn float(float: f64) -> Self {
+        if float.is_finite() {
+            Self::F64(float)
+        } else {
+            Self::Null
+        }
+    }
+
+    /// Object with the given entries, which may come in any order but must have unique keys.
+    pub(crate) fn object(mut entries: Vec<(String, Node)>) -> Result<Self, ConvertError> {
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        if let Some(pair) = entries.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            return Err(ConvertError::DuplicateKey(pair[0].0.clone()));
+        }
+        Ok(Self::Object(entries))
+    }
+
+    fn write(&self, output: &mut Vec<u8>) {
+        match self {
+            Self::Bool(value) => output.extend_from_slice(&[tag::BOOL, *value as u8]),
+            Self::F64(value) => {
+                output.extend_from_slice(&[tag::NUMBER, tag::F64]);
+                output.extend_from_slice(&value.to_le_bytes());
+            }
+            Self::I64(value) => {
+                output.extend_from_slice(&[tag::NUMBER, tag::I64]);
+                output.extend_from_slice(&value.to_le_bytes());
+            }
+            Self::U64(value) => {
+                output.extend_from_slice(&[tag::NUMBER, tag::U64]);
+                output.extend_from_slice(&value.to_le_bytes());
+            }
+            Self::Literal(literal) => {
+                output.extend_from_slice(&[tag::NUMBER, tag::LITERAL]);
+                write_bytes(output, literal.as_bytes());
+            }
+            Self::String(string) => {
+                output.push(tag::STRING);
+                write_bytes(output, string.as_bytes());
+            }
+            Self::Array(elements) => {
+                output.push(tag::ARRAY);
+                output.extend_from_slice(&(elements.len() as u32).to_le_bytes());
+                for element in elements {
+                    element.write(output);
+                }
+            }
+            Self::Object(entries) => {
+                output.push(tag::OBJECT);
+                output.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+                for (key, value) in entries {
+                    write_bytes(output, key.as_bytes());
+                    value.write(output);
+                }
+            }
+            Self::Null => output.push(tag::NULL),
+            Self::Bytes(bytes) => {
+                output.push(tag::BYTES);
+                write_bytes(output, bytes);
+            }
+        }
+    }
+}
+
+fn write_bytes(output: &mut Vec<u8>, bytes: &[u8]) {
+    output.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    output.extend_from_slice(bytes);
+}
+
+impl Deserializer<'_> {
+    fn node(&mut self) -> Result<Node, DecodeError> {
+        match self.read_u8()? {
+            tag::BOOL => match self.read_u8()? {
+                0 => Ok(Node::Bool(false)),
+                1 => Ok(Node::Bool(true)),
+                byte => Err(DecodeError::InvalidBool(byte)),
+            },
+            tag::NUMBER => match self.read_u8()? {
+                tag::F64 => {
+                    let float = f64::from_le_bytes(self.take_array()?);
+                    if !float.is_finite() {
+                        return Err(DecodeError::InvalidNumber(float.to_string()));
+                    }
+                    Ok(Node::F64(float))
+                }
+                tag::I64 => Ok(Node::I64(i64::from_le_bytes(self.take_array()?))),
+                tag::U64 => Ok(Node::U64(u64::from_le_bytes(self.take_array()?))),
+                tag::LITERAL => {
+                    let literal = self.read_str()?;
+                    if literal.parse::<serde_json::Number>().is_err() {
+                        return Err(DecodeError::InvalidNumber(literal.to_owned()));
+                    }
+                    Ok(Node::Literal(literal.to_owned()))
+                }
+                tag => Err(DecodeError::InvalidNumberTag(tag)),
+            },
+            tag::STRING => Ok(Node::String(self.read_str()?.to_owned())),
+            tag::ARRAY => {
+                let len = self.read_len()?;
+                self.enter()?;
+                let mut elements = Vec::with_capacity(len.min(4096));
+                for _ in 0..len {
+                    elements.push(self.node()?);
+                }
+                self.depth -= 1;
+                Ok(Node::Array(elements))
+            }
+            tag::OBJECT => {
+                let len = self.read_len()?;
+                self.enter()?;
+                let mut keys = BTreeSet::new();
+                let mut entries = Vec::with_capacity(len.min(4096));
+                for _ in 0..len {
+                    let key = self.read_str()?;
+                    if !keys.insert(key) {
+                        return Err(DecodeError::DuplicateKey(key.to_owned()));
+                    }
+                    entries.push((key.to_owned(), self.node()?));
+                }
+                self.depth -= 1;
+                entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+                Ok(Node::Object(entries))
+            }
+            tag::NULL => Ok(Node::Null),
+            tag::BYTES => Ok(Node::Bytes(self.read_bytes()?.to_vec())),
+            // The other formats get the string of the number, as a `Value` does.
+            tag::NUMERIC => Ok(Node::String(numeric_literal(self.read_str()?)?.to_owned())),
+            tag => Err(DecodeError::InvalidTag(tag)),
+        }
+    }
+}
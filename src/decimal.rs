@@ -0,0 +1,290 @@
+// Copyright 2025 Kore Ledger
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Fixed-point decimal numbers for amounts and quantities.
+//!
+//! A `Decimal<SCALE>` stores an `i128` number of `10^-SCALE` units, so every operation is exact
+//! integer arithmetic and gives the same result on every evaluator. It is serialized as a
+//! string, e.g. `"12.5"`, so the value never goes through a float. The wire format can give it
+//! a dedicated tag, see `ser::Serializer::with_dedicated_tags`.
+
+use std::{fmt, str::FromStr};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{bigint::I256, error::NumberError, numeric};
+
+/// Fixed-point decimal number with `SCALE` decimal places.
+///
+/// Arithmetic is checked: operations return `None` on overflow or division by zero, and
+/// results with more than `SCALE` decimal places are truncated towards zero. Products and
+/// quotients are computed on 256 bits, so only a result that does not fit overflows.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+pub struct Decimal<const SCALE: u32 = 18>(i128);
+
+impl<const SCALE: u32> Decimal<SCALE> {
+    /// Number of units in `1`.
+    const FACTOR: i128 = 10i128.pow(SCALE);
+    /// `0`.
+    pub const ZERO: Self = Self(0);
+    /// `1`.
+    pub const ONE: Self = Self(Self::FACTOR);
+    /// Largest representable value.
+    pub const MAX: Self = Self(i128::MAX);
+    /// Smallest representable value.
+    pub const MIN: Self = Self(i128::MIN);
+
+    /// Decimal from a number of `10^-SCALE` units, e.g. `Decimal::<2>::from_units(150)` is
+    /// `1.5`.
+    pub const fn from_units(units: i128) -> Self {
+        Self(units)
+    }
+
+    /// Number of `10^-SCALE` units.
+    pub const fn units(self) -> i128 {
+        self.0
+    }
+
+    /// Decimal from an integer, or `None` if it does not fit.
+    pub fn from_integer(integer: i128) -> Option<Self> {
+        integer.checked_mul(Self::FACTOR).map(Self)
+    }
+
+    /// Integer part, truncated towards zero.
+    pub fn trunc(self) -> i128 {
+        self.0 / Self::FACTOR
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        self.0.checked_add(other.0).map(Self)
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.0.checked_sub(other.0).map(Self)
+    }
+
+    pub fn checked_mul(self, other: Self) -> Option<Self> {
+        I256::from(self.0)
+            .checked_mul(I256::from(other.0))?
+            .checked_div(I256::from(Self::FACTOR))?
+            .to_i128()
+            .map(Self)
+    }
+
+    pub fn checked_div(self, other: Self) -> Option<Self> {
+        I256::from(self.0)
+            .checked_mul(I256::from(Self::FACTOR))?
+            .checked_div(I256::from(other.0))?
+            .to_i128()
+            .map(Self)
+    }
+
+    pub fn checked_neg(self) -> Option<Self> {
+        self.0.checked_neg().map(Self)
+    }
+
+    pub fn checked_abs(self) -> Option<Self> {
+        self.0.checked_abs().map(Self)
+    }
+}
+
+/// Shortest representation without exponent, e.g. `-12.5` or `3`.
+impl<const SCALE: u32> fmt::Display for Decimal<SCALE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let units = self.0.unsigned_abs();
+        let factor = Self::FACTOR.unsigned_abs();
+        let (integer, fraction) = (units / factor, units % factor);
+        if fraction == 0 {
+            return write!(f, "{}{}", sign, integer);
+        }
+        let fraction = format!("{:0width$}", fraction, width = SCALE as usize);
+        write!(f, "{}{}.{}", sign, integer, fraction.trim_end_matches('0'))
+    }
+}
+
+impl<const SCALE: u32> FromStr for Decimal<SCALE> {
+    type Err = NumberError;
+
+    /// Parses `[-+]digits[.digits]`. Exponents and more than `SCALE` decimal places are
+    /// rejected.
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let invalid = || NumberError::Invalid(string.to_owned());
+        let (negative, digits) = match string.as_bytes().first() {
+            Some(b'-') => (true, &string[1..]),
+            Some(b'+') => (false, &string[1..]),
+            _ => (false, string),
+        };
+        let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        let is_digits = |part: &str| part.bytes().all(|byte| byte.is_ascii_digit());
+        if integer.is_empty() || !is_digits(integer) || !is_digits(fraction) {
+            return Err(invalid());
+        }
+        if digits.ends_with('.') {
+            return Err(invalid());
+        }
+        if fraction.len() > SCALE as usize {
+            return Err(NumberError::Scale(SCALE));
+        }
+        let padding = 10i128.pow(SCALE - fraction.len() as u32);
+        let fraction = match fraction {
+            "" => 0,
+            fraction => fraction.parse::<i128>().map_err(|_| invalid())?,
+        };
+        // The units are accumulated with their sign, so `Decimal::MIN` parses back.
+        let sign = if negative { -1 } else { 1 };
+        integer
+            .parse::<i128>()
+            .ok()
+            .and_then(|integer| integer.checked_mul(Self::FACTOR))
+            .and_then(|integer| integer.checked_mul(sign))
+            .and_then(|integer| integer.checked_add(sign * fraction * padding))
+            .map(Self)
+            .ok_or(NumberError::Overflow)
+    }
+}
+
+impl<const SCALE: u32> Serialize for Decimal<SCALE> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        numeric::serialize(self, serializer)
+    }
+}
+
+/// Accepts number strings, integers and exact JSON numbers.
+impl<'de, const SCALE: u32> Deserialize<'de> for Decimal<SCALE> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        numeric::deserialize(deserializer)
+    }
+}
+
+#[cfg(feature = "schema")]
+impl<const SCALE: u32> schemars::JsonSchema for Decimal<SCALE> {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        format!("Decimal{}", SCALE).into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        let pattern = match SCALE {
+            0 => "^[-+]?[0-9]+$".to_owned(),
+            scale => format!("^[-+]?[0-9]+(\\.[0-9]{{1,{}}})?$", scale),
+        };
+        schemars::json_schema!({ "type": "string", "pattern": pattern })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    type Amount = Decimal<2>;
+
+    fn amount(string: &str) -> Amount {
+        string.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        assert_eq!(amount("12.5"), Amount::from_units(1250));
+        assert_eq!(amount("-0.05").units(), -5);
+        assert_eq!(amount("+3").to_string(), "3");
+        assert_eq!(amount("-1.50").to_string(), "-1.5");
+        assert_eq!("1.234".parse::<Amount>(), Err(NumberError::Scale(2)));
+        for invalid in ["", "-", ".5", "1.", "1e3", "1.2.3", "0x10", " 1"] {
+            assert!(matches!(
+                invalid.parse::<Amount>(),
+                Err(NumberError::Invalid(_))
+            ));
+        }
+        assert_eq!(
+            "10000000000000000000000000000000000000".parse::<Amount>(),
+            Err(NumberError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        assert_eq!(
+            amount("1.25").checked_add(amount("2.5")),
+            Some(amount("3.75"))
+        );
+        assert_eq!(amount("1").checked_sub(amount("2.5")), Some(amount("-1.5")));
+        assert_eq!(
+            amount("1.5").checked_mul(amount("1.5")),
+            Some(amount("2.25"))
+        );
+        assert_eq!(amount("10").checked_div(amount("3")), Some(amount("3.33")));
+        assert_eq!(amount("1").checked_div(Amount::ZERO), None);
+        assert_eq!(Amount::MAX.checked_add(Amount::from_units(1)), None);
+        assert_eq!(amount("-7.99").trunc(), -7);
+        assert_eq!(
+            amount("-1.5").checked_mul(amount("0.03")),
+            Some(amount("-0.04"))
+        );
+    }
+
+    #[test]
+    fn test_default_scale() {
+        let decimal = |string: &str| string.parse::<Decimal>().unwrap();
+        assert_eq!(
+            decimal("20").checked_mul(decimal("10")),
+            Some(decimal("200"))
+        );
+        assert_eq!(
+            decimal("200").checked_div(decimal("2")),
+            Some(decimal("100"))
+        );
+        assert_eq!(
+            decimal("1").checked_div(decimal("3")),
+            Some(decimal("0.333333333333333333"))
+        );
+        assert_eq!(
+            decimal("-123456789.5").checked_mul(decimal("1000")),
+            Some(decimal("-123456789500"))
+        );
+        assert_eq!(Decimal::MAX.checked_mul(decimal("2")), None);
+        assert_eq!(decimal("1").checked_div(Decimal::ZERO), None);
+        assert_eq!(Decimal::MAX.checked_div(decimal("0.5")), None);
+
+        for limit in [Decimal::MIN, Decimal::MAX] {
+            assert_eq!(limit.to_string().parse::<Decimal>(), Ok(limit));
+        }
+        assert_eq!(
+            "-170141183460469231731.687303715884105729".parse::<Decimal>(),
+            Err(NumberError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_serde() {
+        let value = serde_json::to_value(amount("-2.5")).unwrap();
+        assert_eq!(value, json!("-2.5"));
+        assert_eq!(serde_json::from_value::<Amount>(value).unwrap(), amount("-2.5"));
+        let amounts: Vec<Amount> = serde_json::from_value(json!(["0.01", 3])).unwrap();
+        assert_eq!(amounts, [amount("0.01"), amount("3")]);
+
+        let value: serde_json::Value = serde_json::from_str("0.12").unwrap();
+        assert_eq!(serde_json::from_value::<Amount>(value).unwrap(), amount("0.12"));
+        assert!(serde_json::from_value::<Amount>(json!(0.125)).is_err());
+
+        let bytes = borsh::to_vec(&amount("4.2")).unwrap();
+        assert_eq!(Amount::try_from_slice(&bytes).unwrap(), amount("4.2"));
+    }
+}
@@ -1,8 +1,13 @@
 // Copyright 2025 Kore Ledger
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use thiserror::Error;
 
+use crate::ValueWrapper;
+
 #[derive(Error, Debug)]
 pub(crate) enum Error {
     #[error("Serialization error: {0}")]
@@ -10,3 +15,260 @@ pub(crate) enum Error {
     #[error("Deserialization error: {0}")]
     Deserialization(String),
 }
+
+/// Stable error codes.
+///
+/// Codes below `USER` are reserved for the SDK. Contracts should use codes starting at `USER`.
+pub mod codes {
+    /// No specific code was given, e.g. an error built from a plain string.
+    pub const UNSPECIFIED: u32 = 0;
+    /// The contract did not report success nor an error.
+    pub const NOT_SUCCESSFUL: u32 = 1;
+    /// The contract logic panicked.
+    pub const CONTRACT_PANIC: u32 = 2;
+    /// The state received from the host can not be decoded.
+    pub const STATE_DESERIALIZATION: u32 = 10;
+    /// The state received from the host does not match the contract state type.
+    pub const STATE_CONVERSION: u32 = 11;
+    /// The init state received from the host can not be decoded.
+    pub const INIT_STATE_DESERIALIZATION: u32 = 12;
+    /// The event received from the host can not be decoded.
+    pub const EVENT_DESERIALIZATION: u32 = 13;
+    /// The event received from the host does not match the contract event type.
+    pub const EVENT_CONVERSION: u32 = 14;
+    /// The final state produced by the contract can not be encoded.
+    pub const FINAL_STATE_CONVERSION: u32 = 15;
+    /// The contract result can not be returned to the host.
+    pub const RESULT_SERIALIZATION: u32 = 16;
+    /// The execution context received from the host can not be decoded.
+    pub const CONTEXT_DESERIALIZATION: u32 = 17;
+    /// The state or event received from the host exceeds the `DecodeLimits`.
+    pub const DECODE_LIMIT: u32 = 18;
+    /// An output event emitted by the contract can not be encoded.
+    pub const OUTPUT_CONVERSION: u32 = 19;
+    /// The state received from the host does not satisfy the schema or invariants.
+    pub const INVALID_STATE: u32 = 20;
+    /// The event received from the host does not satisfy the schema or invariants.
+    pub const INVALID_EVENT: u32 = 21;
+    /// The final state produced by the contract does not satisfy the schema or invariants.
+    pub const INVALID_FINAL_STATE: u32 = 22;
+    /// The stored state can not be migrated to the current state version.
+    pub const STATE_MIGRATION: u32 = 23;
+    /// The stored state version is newer than the contract state version.
+    pub const UNSUPPORTED_STATE_VERSION: u32 = 24;
+    /// A state or event contains a floating-point number not allowed by the `FloatPolicy`.
+    pub const FLOAT_NOT_ALLOWED: u32 = 25;
+    /// The event is not allowed by the lifecycle state machine in the current status.
+    pub const ILLEGAL_TRANSITION: u32 = 30;
+    /// The query received from the host can not be decoded.
+    pub const QUERY_DESERIALIZATION: u32 = 40;
+    /// The query received from the host does not match the contract query type.
+    pub const QUERY_CONVERSION: u32 = 41;
+    /// The response produced by the query logic can not be encoded.
+    pub const RESPONSE_CONVERSION: u32 = 42;
+    /// First code available for contract defined errors.
+    pub const USER: u32 = 1000;
+}
+
+/// Category of a contract error.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize,
+)]
+pub enum ErrorCategory {
+    /// The data does not satisfy the contract rules.
+    Validation,
+    /// The signer is not allowed to perform the action.
+    Authorization,
+    /// The event is not valid for the subject.
+    InvalidEvent,
+    /// Failure of the SDK or the contract itself.
+    Internal,
+}
+
+/// Contract error returned to the host.
+#[derive(
+    Error, Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize,
+)]
+#[error("{message} (code {code})")]
+pub struct ContractError {
+    /// Stable numeric code, see `codes`.
+    pub code: u32,
+    /// Category of the error.
+    pub category: ErrorCategory,
+    /// Human readable message.
+    pub message: String,
+    /// Optional structured details.
+    pub details: Option<ValueWrapper>,
+}
+
+impl ContractError {
+    pub fn new(category: ErrorCategory, code: u32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            category,
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    pub fn validation(code: u32, message: impl Into<String>) -> Self {
+        Self::new(ErrorCategory::Validation, code, message)
+    }
+
+    pub fn authorization(code: u32, message: impl Into<String>) -> Self {
+        Self::new(ErrorCategory::Authorization, code, message)
+    }
+
+    pub fn invalid_event(code: u32, message: impl Into<String>) -> Self {
+        Self::new(ErrorCategory::InvalidEvent, code, message)
+    }
+
+    pub fn internal(code: u32, message: impl Into<String>) -> Self {
+        Self::new(ErrorCategory::Internal, code, message)
+    }
+
+    /// Attaches structured details to the error.
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(ValueWrapper(details));
+        self
+    }
+}
+
+/// Plain messages are validation errors without a specific code.
+impl From<String> for ContractError {
+    fn from(message: String) -> Self {
+        Self::validation(codes::UNSPECIFIED, message)
+    }
+}
+
+impl From<&str> for ContractError {
+    fn from(message: &str) -> Self {
+        Self::validation(codes::UNSPECIFIED, message)
+    }
+}
+
+/// Errors applying a JSON Patch.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum PatchError {
+    #[error("Invalid JSON Pointer: {0}")]
+    InvalidPointer(String),
+    #[error("Invalid array index in path: {0}")]
+    InvalidIndex(String),
+    #[error("Path not found: {0}")]
+    PathNotFound(String),
+    #[error("Can not move {0} into its own child {1}")]
+    InvalidMove(String, String),
+    #[error("Test operation failed at path: {0}")]
+    TestFailed(String),
+}
+
+/// Errors decoding the `ValueWrapper` wire format into a type.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    #[error("Unexpected end of input")]
+    UnexpectedEnd,
+    #[error("Trailing bytes after the value")]
+    TrailingBytes,
+    #[error("Invalid value tag: {0}")]
+    InvalidTag(u8),
+    #[error("Invalid number tag: {0}")]
+    InvalidNumberTag(u8),
+    #[error("Invalid number: {0}")]
+    InvalidNumber(String),
+    #[error("Invalid bool: {0}")]
+    InvalidBool(u8),
+    #[error("Invalid UTF-8 string")]
+    InvalidUtf8,
+    #[error("Duplicate object key: {0}")]
+    DuplicateKey(String),
+    #[error("Nesting depth exceeds the limit of {0}")]
+    DepthLimit(usize),
+    #[error("Array or object length exceeds the limit of {0} elements")]
+    ElementLimit(usize),
+    #[error("String length exceeds the limit of {0} bytes")]
+    StringLimit(usize),
+    #[error("Input size exceeds the limit of {0} bytes")]
+    SizeLimit(usize),
+    /// The data is well formed but does not match the target type.
+    #[error("{0}")]
+    Message(String),
+}
+
+impl DecodeError {
+    /// Whether the input was rejected for exceeding the `DecodeLimits`.
+    pub fn is_limit(&self) -> bool {
+        matches!(
+            self,
+            Self::DepthLimit(_) | Self::ElementLimit(_) | Self::StringLimit(_) | Self::SizeLimit(_)
+        )
+    }
+}
+
+impl From<DecodeError> for std::io::Error {
+    fn from(error: DecodeError) -> Self {
+        let kind = match error {
+            DecodeError::UnexpectedEnd => std::io::ErrorKind::UnexpectedEof,
+            _ => std::io::ErrorKind::InvalidData,
+        };
+        std::io::Error::new(kind, error)
+    }
+}
+
+impl serde::de::Error for DecodeError {
+    fn custom<T: std::fmt::Display>(message: T) -> Self {
+        Self::Message(message.to_string())
+    }
+}
+
+/// Errors encoding a type into the `ValueWrapper` wire format.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum EncodeError {
+    #[error("Object keys must be strings")]
+    KeyMustBeString,
+    #[error("{0}")]
+    Message(String),
+}
+
+impl serde::ser::Error for EncodeError {
+    fn custom<T: std::fmt::Display>(message: T) -> Self {
+        Self::Message(message.to_string())
+    }
+}
+
+/// Errors converting between the `ValueWrapper` wire format and CBOR or MessagePack.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ConvertError {
+    #[error("Invalid wire format: {0}")]
+    Decode(#[from] DecodeError),
+    #[error("{format} error: {message}")]
+    Format {
+        format: &'static str,
+        message: String,
+    },
+    #[error("Duplicate object key: {0}")]
+    DuplicateKey(String),
+    /// The input has a value that the target format can not represent.
+    #[error("Unsupported value: {0}")]
+    Unsupported(String),
+}
+
+/// Errors parsing a `Decimal`, `U256` or `I256`.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum NumberError {
+    #[error("Invalid number: {0}")]
+    Invalid(String),
+    #[error("Too many decimal places, at most {0} are allowed")]
+    Scale(u32),
+    #[error("Number out of range")]
+    Overflow,
+}
+
+/// Value that does not match a JSON Schema.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("Invalid value at '{path}': {message}")]
+pub struct ValidationError {
+    /// JSON Pointer to the invalid value.
+    pub path: String,
+    /// Reason of the failure.
+    pub message: String,
+}
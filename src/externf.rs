@@ -1,18 +1,127 @@
 // Copyright 2025 Kore Ledger 
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+use std::io;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::{ContractError, ValueWrapper};
+
+/// Version of the host ABI implemented by the SDK: the imported host functions, the signature
+/// of the entry points and the layout of their results.
+///
+/// * `1` - Byte by byte transfers (`read_byte`/`write_byte`), selected with the `legacy-abi`
+///   feature for older Kore nodes. `main_function` does not receive the execution
+///   information, so contracts get the default `Context` signer, identifiers, sn and
+///   timestamp. Results are written in the version `1` layout.
+/// * `3` - Bulk transfers (`read_bytes`/`write_bytes`) between the host and the wasm linear
+///   memory. `main_function` receives the execution information and results are written in
+///   the version `3` layout.
+///
+/// Result layouts, in borsh:
+///
+/// * `1` - `main_function` returns `{ final_state, success: bool, error: String }` and
+///   `init_check_function` returns `{ success: bool, error: String }`. The error is empty on
+///   success and the JSON of the `ContractError` otherwise. Output events are dropped.
+/// * `3` - `main_function` returns `{ final_state, success: bool, error:
+///   Option<ContractError>, outputs: Vec<Value> }` and `init_check_function` returns
+///   `{ success: bool, error: Option<ContractError> }`.
+///
+/// The patch result mode and the batch, query and constructor entry points were added in
+/// version `3` and always use its layout.
+#[cfg(feature = "legacy-abi")]
+pub const ABI_VERSION: u32 = 1;
+#[cfg(not(feature = "legacy-abi"))]
+pub const ABI_VERSION: u32 = 3;
+
+/// Exports the host ABI version implemented by the contract, so the host can select the
+/// imports it has to provide.
+#[unsafe(no_mangle)]
+pub extern "C" fn kore_abi_version() -> u32 {
+    ABI_VERSION
+}
+
 // Extern functions for the wasm module.
+#[cfg(not(any(test, feature = "testing")))]
 unsafe extern "C" {
     // Host functions
     // Read the byte from the context indicated by the pointer
+    #[cfg(feature = "legacy-abi")]
     pub(crate) fn read_byte(pointer: i32) -> u8;
+    // Copy `len` bytes of the context structure starting with the indicated pointer into the
+    // linear memory at `dest`
+    #[cfg(not(feature = "legacy-abi"))]
+    pub(crate) fn read_bytes(pointer: i32, dest: *mut u8, len: i32);
     // Gets the length in bytes of the context structure starting with the indicated pointer
     pub(crate) fn pointer_len(pointer: i32) -> i32;
     // Reserve memory in the context state for later writes
     pub(crate) fn alloc(len: u32) -> i32;
     // Write a byte at the indicated position
+    #[cfg(feature = "legacy-abi")]
     pub(crate) fn write_byte(ptr: u32, offset: u32, data: u8);
+    // Copy `len` bytes of the linear memory at `src` into the reserved memory at `ptr`
+    #[cfg(not(feature = "legacy-abi"))]
+    pub(crate) fn write_bytes(ptr: u32, src: *const u8, len: u32);
     // Println
-    #[allow(dead_code)]
     pub(crate) fn cout(ptr: u32);
 }
+
+// In-process implementation of the host functions for native tests.
+#[cfg(any(test, feature = "testing"))]
+#[allow(unused_imports)]
+pub(crate) use crate::testing::host::{
+    alloc, cout, pointer_len, read_byte, read_bytes, write_byte, write_bytes,
+};
+
+/// Writes the error of a `main_function` or `init_check_function` result in the layout of
+/// `ABI_VERSION`.
+pub(crate) fn serialize_error<W: io::Write>(
+    error: &Option<ContractError>,
+    writer: &mut W,
+) -> io::Result<()> {
+    if cfg!(feature = "legacy-abi") {
+        let message = match error {
+            Some(error) => serde_json::to_string(error).map_err(io::Error::other)?,
+            None => String::new(),
+        };
+        message.serialize(writer)
+    } else {
+        error.serialize(writer)
+    }
+}
+
+/// Reads the error of a `main_function` or `init_check_function` result in the layout of
+/// `ABI_VERSION`. Version `1` errors that are not a `ContractError` are plain messages.
+pub(crate) fn deserialize_error<R: io::Read>(
+    reader: &mut R,
+) -> io::Result<Option<ContractError>> {
+    if cfg!(feature = "legacy-abi") {
+        let message = String::deserialize_reader(reader)?;
+        Ok((!message.is_empty()).then(|| {
+            serde_json::from_str(&message).unwrap_or_else(|_| ContractError::from(message))
+        }))
+    } else {
+        Option::deserialize_reader(reader)
+    }
+}
+
+/// Writes the output events of a `main_function` result in the layout of `ABI_VERSION`.
+pub(crate) fn serialize_outputs<W: io::Write>(
+    outputs: &Vec<ValueWrapper>,
+    writer: &mut W,
+) -> io::Result<()> {
+    if cfg!(feature = "legacy-abi") {
+        Ok(())
+    } else {
+        outputs.serialize(writer)
+    }
+}
+
+/// Reads the output events of a `main_function` result in the layout of `ABI_VERSION`.
+pub(crate) fn deserialize_outputs<R: io::Read>(reader: &mut R) -> io::Result<Vec<ValueWrapper>> {
+    if cfg!(feature = "legacy-abi") {
+        Ok(Vec::new())
+    } else {
+        Vec::deserialize_reader(reader)
+    }
+}
@@ -1,14 +1,54 @@
 // Copyright 2025 Kore Ledger
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+pub mod bigint;
+pub mod bytes;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+pub mod de;
+pub mod decimal;
 mod error;
 mod externf;
+pub mod log;
+pub mod logic;
+pub mod migration;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+mod numeric;
+mod options;
+mod panic_hook;
+pub mod patch;
+#[cfg(feature = "schema")]
+pub mod schema;
+pub mod ser;
+pub mod state_machine;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod validation;
 mod value_wrapper;
 use borsh::{BorshDeserialize, BorshSerialize};
 use error::Error;
 use serde::{Deserialize, Serialize};
 
+pub use self::bigint::{I256, U256};
+pub use self::bytes::Bytes;
+pub use self::de::{DecodeLimits, from_bytes};
+pub use self::decimal::Decimal;
+pub use self::error::{
+    ContractError, ConvertError, DecodeError, EncodeError, ErrorCategory, NumberError,
+    PatchError, ValidationError, codes,
+};
+pub use self::externf::ABI_VERSION;
+pub use self::logic::{ContractLogic, InitLogic, reject};
+pub use self::migration::Migrations;
+pub use self::options::{
+    BatchMode, ExecutionOptions, FloatPolicy, Invariant, ResultMode, StateFallback,
+};
+pub use self::patch::StatePatch;
+pub use self::ser::to_bytes;
+pub use self::state_machine::StateMachine;
 pub use self::value_wrapper::ValueWrapper;
+pub use kore_contract_sdk_macros::contract;
 
 /// Contrat execution context.
 #[derive(Serialize, Deserialize, Debug)]
@@ -19,6 +59,51 @@ pub struct Context<State, Event> {
     pub event: Event,
     /// Is the sender of the event the owner of the contract
     pub is_owner: bool,
+    /// Public key of the signer of the event
+    pub signer: String,
+    /// Identifier of the subject
+    pub subject_id: String,
+    /// Identifier of the governance of the subject
+    pub governance_id: String,
+    /// Sequence number of the event
+    pub sn: u64,
+    /// Timestamp of the event provided by the ledger, in nanoseconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+/// Context of the init entry point of a constructor contract.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct InitContext<State> {
+    /// Initial state proposed for the subject
+    pub init_state: State,
+    /// Public key of the creator of the subject
+    pub creator: String,
+    /// Identifier of the subject
+    pub subject_id: String,
+}
+
+/// Internal init information received from the host.
+#[derive(BorshSerialize, BorshDeserialize)]
+struct InitInfoBorsh {
+    /// Public key of the creator of the subject.
+    pub creator: String,
+    /// Identifier of the subject.
+    pub subject_id: String,
+}
+
+/// Internal execution information received from the host.
+#[derive(BorshSerialize, BorshDeserialize, Default, Clone)]
+struct ContextInfoBorsh {
+    /// Public key of the signer of the event.
+    pub signer: String,
+    /// Identifier of the subject.
+    pub subject_id: String,
+    /// Identifier of the governance of the subject.
+    pub governance_id: String,
+    /// Sequence number of the event.
+    pub sn: u64,
+    /// Timestamp of the event, in nanoseconds since the Unix epoch.
+    pub timestamp: u64,
 }
 
 /// Contract execution result.
@@ -29,7 +114,22 @@ pub struct ContractResult<State> {
     /// Is the contract execution successful?
     pub success: bool,
     /// Contract error
-    pub error: String
+    pub error: Option<ContractError>,
+    /// Output events emitted by the contract, see `emit`. They are returned to the host only
+    /// if the execution succeeds.
+    pub outputs: Vec<ValueWrapper>,
+}
+
+/// Result of one event of a batch.
+#[derive(Serialize, Deserialize, BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct EventResult {
+    /// Was the event applied? Events rolled back by `BatchMode::AllOrNothing` are not applied
+    /// and have no error and no output events.
+    pub success: bool,
+    /// Error of the event
+    pub error: Option<ContractError>,
+    /// Output events emitted by the contract for the event.
+    pub outputs: Vec<ValueWrapper>,
 }
 
 /// Contract init result.
@@ -38,53 +138,197 @@ pub struct ContractInitCheck {
     /// Is the contract init successful?
     pub success: bool,
     /// Contract error
-    pub error: String,
+    pub error: Option<ContractError>,
 }
 
-/// Internal contract execution result used for borsh serialization.
-#[derive(BorshSerialize)]
+/// Internal contract execution result used for borsh serialization, in the layout of
+/// `ABI_VERSION`.
+#[derive(BorshSerialize, BorshDeserialize)]
 struct ContractResultBorsh {
     /// Final state of the contract.
     pub final_state: ValueWrapper,
     /// Is the contract execution successful?
     pub success: bool,
     /// Contract error
-    pub error: String
+    #[borsh(
+        serialize_with = "externf::serialize_error",
+        deserialize_with = "externf::deserialize_error"
+    )]
+    pub error: Option<ContractError>,
+    /// Output events emitted by the contract.
+    #[borsh(
+        serialize_with = "externf::serialize_outputs",
+        deserialize_with = "externf::deserialize_outputs"
+    )]
+    pub outputs: Vec<ValueWrapper>,
 }
 
 /// Internal contract execution result implementation for errors.
 impl ContractResultBorsh {
-    pub fn error(error: &str) -> Self {
+    pub fn error(error: ContractError) -> Self {
         Self {
             final_state: ValueWrapper(serde_json::Value::Null),
             success: false,
-            error: error.to_owned()
+            error: Some(error),
+            outputs: Vec::new(),
         }
     }
 }
 
-/// Internal contract execution result used for borsh serialization.
+/// Final state or query response returned to the host, encoded directly or through its
+/// `Value`.
+enum FinalState {
+    Encoded(Vec<u8>),
+    Value(serde_json::Value),
+}
+
+impl BorshSerialize for FinalState {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        match self {
+            FinalState::Encoded(bytes) => writer.write_all(bytes),
+            FinalState::Value(value) => value_wrapper::serialize_value(value, writer),
+        }
+    }
+}
+
+/// `ContractResultBorsh` with a `FinalState`, for writing only.
 #[derive(BorshSerialize)]
+struct ContractEncodedResultBorsh {
+    final_state: FinalState,
+    success: bool,
+    #[borsh(serialize_with = "externf::serialize_error")]
+    error: Option<ContractError>,
+    #[borsh(serialize_with = "externf::serialize_outputs")]
+    outputs: Vec<ValueWrapper>,
+}
+
+/// Internal contract execution result used for borsh serialization in patch mode.
+#[derive(BorshSerialize, BorshDeserialize)]
+struct ContractPatchResultBorsh {
+    /// Changes over the received state.
+    pub patch: StatePatch,
+    /// Is the contract execution successful?
+    pub success: bool,
+    /// Contract error
+    pub error: Option<ContractError>,
+    /// Output events emitted by the contract.
+    pub outputs: Vec<ValueWrapper>,
+}
+
+/// Internal contract execution result implementation for errors.
+impl ContractPatchResultBorsh {
+    pub fn error(error: ContractError) -> Self {
+        Self {
+            patch: StatePatch::Unchanged,
+            success: false,
+            error: Some(error),
+            outputs: Vec::new(),
+        }
+    }
+}
+
+/// Internal batch execution result used for borsh serialization. The final state is a
+/// `FinalState` when written and a `ValueWrapper` when read.
+#[derive(BorshSerialize, BorshDeserialize)]
+struct ContractBatchResultBorsh<S> {
+    /// Final state after the applied events.
+    pub final_state: S,
+    /// Were all the events applied?
+    pub success: bool,
+    /// Error of the failed event, or of the batch
+    pub error: Option<ContractError>,
+    /// What happened to the batch when an event failed.
+    pub mode: BatchMode,
+    /// Results of the events run, in order. Events after a failed one are not run.
+    pub events: Vec<EventResult>,
+}
+
+/// Internal batch execution result implementation for errors.
+impl ContractBatchResultBorsh<FinalState> {
+    pub fn error(error: ContractError, mode: BatchMode) -> Self {
+        Self {
+            final_state: FinalState::Value(serde_json::Value::Null),
+            success: false,
+            error: Some(error),
+            mode,
+            events: Vec::new(),
+        }
+    }
+}
+
+/// Internal query result used for borsh serialization. The response is a `FinalState` when
+/// written and a `ValueWrapper` when read.
+#[derive(BorshSerialize, BorshDeserialize)]
+struct ContractQueryResultBorsh<R> {
+    /// Response of the query.
+    pub response: R,
+    /// Is the query successful?
+    pub success: bool,
+    /// Query error
+    pub error: Option<ContractError>,
+}
+
+/// Internal query result implementation for errors.
+impl ContractQueryResultBorsh<FinalState> {
+    pub fn error(error: ContractError) -> Self {
+        Self {
+            response: FinalState::Value(serde_json::Value::Null),
+            success: false,
+            error: Some(error),
+        }
+    }
+}
+
+/// Internal contract execution result used for borsh serialization, in the layout of
+/// `ABI_VERSION`.
+#[derive(BorshSerialize, BorshDeserialize)]
 struct ContractInitCheckBorsh {
     /// Is the contract execution successful?
     pub success: bool,
     /// Contract error
-    pub error: String
+    #[borsh(
+        serialize_with = "externf::serialize_error",
+        deserialize_with = "externf::deserialize_error"
+    )]
+    pub error: Option<ContractError>
 }
 
 /// Internal contract execution result implementation for errors.
 impl ContractInitCheckBorsh {
-    pub fn error(error: &str) -> Self {
+    pub fn error(error: ContractError) -> Self {
         Self {
             success: false,
-            error: error.to_owned()
+            error: Some(error)
         }
     }
 
     pub fn ok() -> Self {
         Self {
             success: true,
-            error: String::default()
+            error: None
+        }
+    }
+}
+
+/// Internal init result used for borsh serialization. The initial state is a `FinalState` when
+/// written and a `ValueWrapper` when read.
+#[derive(BorshSerialize, BorshDeserialize)]
+struct ContractInitResultBorsh<S> {
+    /// Initial state of the subject, possibly modified by the contract.
+    pub init_state: S,
+    /// Is the contract init successful?
+    pub success: bool,
+    /// Contract error
+    pub error: Option<ContractError>,
+}
+
+/// Internal init result implementation for errors.
+impl ContractInitResultBorsh<FinalState> {
+    pub fn error(error: ContractError) -> Self {
+        Self {
+            init_state: FinalState::Value(serde_json::Value::Null),
+            success: false,
+            error: Some(error),
         }
     }
 }
@@ -95,56 +339,299 @@ impl<State> ContractResult<State> {
         Self {
             final_state: state,
             success: false,
-            error: String::default()
+            error: None,
+            outputs: Vec::new(),
         }
     }
+
+    /// Emits an output event, such as "invoice paid", returned to the host next to the final
+    /// state if the execution succeeds.
+    ///
+    /// ```ignore
+    /// if let Err(error) = contract_result.emit(&Output::InvoicePaid { invoice, amount }) {
+    ///     contract_result.error = Some(error);
+    ///     return;
+    /// }
+    /// ```
+    pub fn emit<T: Serialize>(&mut self, output: &T) -> Result<(), ContractError> {
+        self.outputs.push(output_value(output)?);
+        Ok(())
+    }
+}
+
+/// Output events emitted by a contract logic that returns a `Result`, see `ContractLogic`.
+///
+/// ```ignore
+/// fn contract_logic(
+///     context: &Context<State, Event>,
+///     state: &mut State,
+///     outputs: &mut Outputs,
+/// ) -> Result<(), ContractError> {
+///     state.paid = true;
+///     outputs.emit(&Output::InvoicePaid { invoice: state.invoice })
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct Outputs(Vec<ValueWrapper>);
+
+impl Outputs {
+    /// Emits an output event, returned to the host next to the final state if the execution
+    /// succeeds.
+    pub fn emit<T: Serialize>(&mut self, output: &T) -> Result<(), ContractError> {
+        self.0.push(output_value(output)?);
+        Ok(())
+    }
+
+    /// Output events emitted so far.
+    pub fn as_slice(&self) -> &[ValueWrapper] {
+        &self.0
+    }
+}
+
+fn output_value<T: Serialize>(output: &T) -> Result<ValueWrapper, ContractError> {
+    serde_json::to_value(output).map(ValueWrapper).map_err(|_| {
+        ContractError::internal(
+            codes::OUTPUT_CONVERSION,
+            "Can not convert output event into Value",
+        )
+    })
 }
 
 /// Contract execution.
 ///
+/// The host stores the checked init state as it was proposed, without a state version. With
+/// migrations enabled after the first version, use `init_contract_with` instead so the first
+/// event does not migrate the initial state.
+///
 /// # Arguments
 ///
 /// * `state_ptr` - Pointer to the initial state of the contract.
-/// * `callback` - Callback that will be executed with the init contract logic.
+/// * `callback` - Init contract logic, see `InitLogic`.
 ///
 /// # Returns
 ///
 /// * `result_ptr` - Pointer to the init contract execution result.
 ///
-pub fn check_init_data<State, F>(
+pub fn check_init_data<State, F, M>(
     state_ptr: i32,
     callback: F,
 ) -> u32 
 where
     State: for<'a> Deserialize<'a> + Serialize + Clone,
-    F: Fn(&State, &mut ContractInitCheck),
+    F: InitLogic<State, M>,
 {
+    check_init_data_with(state_ptr, FloatPolicy::Allow, callback)
+}
+
+/// Contract init check with a float policy.
+///
+/// # Arguments
+///
+/// * `state_ptr` - Pointer to the initial state of the contract.
+/// * `float_policy` - How floats in the initial state are handled.
+/// * `callback` - Init contract logic, see `InitLogic`.
+///
+/// # Returns
+///
+/// * `result_ptr` - Pointer to the init contract execution result.
+///
+pub fn check_init_data_with<State, F, M>(
+    state_ptr: i32,
+    float_policy: FloatPolicy,
+    callback: F,
+) -> u32
+where
+    State: for<'a> Deserialize<'a> + Serialize + Clone,
+    F: InitLogic<State, M>,
+{
+    panic_hook::install();
     {
-        let error: String;
+        let error: ContractError;
         'process: {
             let Ok(state_value) = deserialize(get_from_context(state_ptr)) else {
-                error = "Can not deserialize State".to_owned();
+                error = ContractError::internal(
+                    codes::STATE_DESERIALIZATION,
+                    "Can not deserialize State",
+                );
                 break 'process;
             };
-            let Ok(state) = serde_json::from_value::<State>(state_value.0) else {
-                error = "Can not convert State from value".to_owned();
+            let state_value = match float_policy.apply(&state_value.0) {
+                Ok(normalized_state) => normalized_state.unwrap_or(state_value.0),
+                Err(float_error) => {
+                    error = float_error;
+                    break 'process;
+                }
+            };
+            let Ok(state) = serde_json::from_value::<State>(state_value) else {
+                error = ContractError::validation(
+                    codes::STATE_CONVERSION,
+                    "Can not convert State from value",
+                );
                 break 'process;
             };
             let mut contract_result = ContractInitCheck::default();
-            callback(&state, &mut contract_result);
+            if let Err(panic_error) =
+                panic_hook::catch(|| callback.run(&state, &mut contract_result))
+            {
+                error = panic_error;
+                break 'process;
+            }
 
-            if !contract_result.success {
-                error = format!("Error running init contract data: {}", contract_result.error);
+            if let Some(contract_error) =
+                execution_error(contract_result.success, contract_result.error)
+            {
+                error = contract_error;
                 break 'process;
             }
 
             let Ok(result_ptr) = store(&ContractInitCheckBorsh::ok()) else {
-                error = "Can not return init contract result".to_owned();
+                error = ContractError::internal(
+                    codes::RESULT_SERIALIZATION,
+                    "Can not return init contract result",
+                );
                 break 'process;
             };
             return result_ptr;
         }
-        store(&ContractInitCheckBorsh::error(&error)).expect("Contract store process failed")
+        store(&ContractInitCheckBorsh::error(error)).expect("Contract store process failed")
+    }
+}
+
+/// Contract constructor.
+///
+/// Unlike `check_init_data`, which only accepts or rejects the initial state, the init logic
+/// returns the initial state of the subject, so it can fill in defaults, derive fields or
+/// canonicalize the proposed state.
+///
+/// # Arguments
+///
+/// * `state_ptr` - Pointer to the initial state proposed for the subject.
+/// * `info_ptr` - Pointer to the init information: creator and subject identifier.
+/// * `callback` - Init logic, `fn(&InitContext<State>) -> Result<State, ContractError>`.
+///
+/// # Returns
+///
+/// * `result_ptr` - Pointer to the init result, with the initial state.
+///
+pub fn init_contract<State, F>(state_ptr: i32, info_ptr: i32, callback: F) -> u32
+where
+    State: for<'a> Deserialize<'a> + Serialize,
+    F: Fn(&InitContext<State>) -> Result<State, ContractError>,
+{
+    init_contract_with(state_ptr, info_ptr, &ExecutionOptions::<State, ()>::default(), callback)
+}
+
+/// Contract constructor with execution options.
+///
+/// The float policy, the state schema and invariant apply to both the proposed and the
+/// returned initial state. With migrations, the proposed state is migrated like an init state
+/// and the returned one is stored with the current state version, so the first event does not
+/// migrate it again.
+///
+/// # Arguments
+///
+/// * `state_ptr` - Pointer to the initial state proposed for the subject.
+/// * `info_ptr` - Pointer to the init information: creator and subject identifier.
+/// * `options` - Execution options, the same ones used by the contract logic.
+/// * `callback` - Init logic, `fn(&InitContext<State>) -> Result<State, ContractError>`.
+///
+/// # Returns
+///
+/// * `result_ptr` - Pointer to the init result, with the initial state.
+///
+pub fn init_contract_with<State, Event, F>(
+    state_ptr: i32,
+    info_ptr: i32,
+    options: &ExecutionOptions<State, Event>,
+    callback: F,
+) -> u32
+where
+    State: for<'a> Deserialize<'a> + Serialize,
+    Event: Serialize,
+    F: Fn(&InitContext<State>) -> Result<State, ContractError>,
+{
+    panic_hook::install();
+    {
+        let error: ContractError;
+        'process: {
+            let Ok(state_value) = deserialize(get_from_context(state_ptr)) else {
+                error = ContractError::internal(
+                    codes::STATE_DESERIALIZATION,
+                    "Can not deserialize State",
+                );
+                break 'process;
+            };
+            let state_value = match options.float_policy.apply(&state_value.0) {
+                Ok(normalized_state) => normalized_state.unwrap_or(state_value.0),
+                Err(float_error) => {
+                    error = float_error;
+                    break 'process;
+                }
+            };
+            let state_value = match &options.migrations {
+                Some(migrations) => match migrations.migrate_init(state_value) {
+                    Ok(migrated_state) => migrated_state,
+                    Err(migration_error) => {
+                        error = migration_error;
+                        break 'process;
+                    }
+                },
+                None => state_value,
+            };
+            let Ok(init_state) = State::deserialize(&state_value) else {
+                error = ContractError::validation(
+                    codes::STATE_CONVERSION,
+                    "Can not convert State from value",
+                );
+                break 'process;
+            };
+            if let Err(state_error) =
+                options.check_state(Some(&state_value), &init_state, codes::INVALID_STATE)
+            {
+                error = state_error;
+                break 'process;
+            }
+            let Ok(info) = deserialize_init_info(get_from_context(info_ptr)) else {
+                error = ContractError::internal(
+                    codes::CONTEXT_DESERIALIZATION,
+                    "Can not deserialize init context",
+                );
+                break 'process;
+            };
+            let context = InitContext {
+                init_state,
+                creator: info.creator,
+                subject_id: info.subject_id,
+            };
+            let init_state = match panic_hook::catch(|| callback(&context)) {
+                Ok(Ok(init_state)) => init_state,
+                Ok(Err(init_error)) | Err(init_error) => {
+                    error = init_error;
+                    break 'process;
+                }
+            };
+            let init_value = match final_value(&init_state, options) {
+                Ok(init_value) => init_value,
+                Err(state_error) => {
+                    error = state_error;
+                    break 'process;
+                }
+            };
+            let result = ContractInitResultBorsh {
+                init_state: FinalState::Value(init_value),
+                success: true,
+                error: None,
+            };
+            let Ok(result_ptr) = store(&result) else {
+                error = ContractError::internal(
+                    codes::RESULT_SERIALIZATION,
+                    "Can not return init contract result",
+                );
+                break 'process;
+            };
+            return result_ptr;
+        }
+        store(&ContractInitResultBorsh::error(error)).expect("Contract store process failed")
     }
 }
 
@@ -153,83 +640,702 @@ where
 /// # Arguments
 ///
 /// * `state_ptr` - Pointer to the initial state of the contract.
+/// * `init_state_ptr` - Pointer to the init state, used if the state does not match `State`
+///   and the options select `StateFallback::InitState`.
 /// * `event_ptr` - Pointer to the event that triggered the contract execution.
 /// * `is_owner` - Is the sender of the event the owner of the contract?
-/// * `callback` - Callback that will be executed with the contract logic.
+/// * `info_ptr` - Pointer to the execution information: signer, subject and governance
+///   identifiers, sequence number and timestamp.
+/// * `callback` - Contract logic, see `ContractLogic`.
 ///
 /// # Returns
 ///
 /// * `result_ptr` - Pointer to the contract execution result.
 ///
-pub fn execute_contract<F, State, Event>(
+pub fn execute_contract<F, M, State, Event>(
     state_ptr: i32,
     init_state_ptr: i32,
     event_ptr: i32,
     is_owner: i32,
+    info_ptr: i32,
     callback: F,
 ) -> u32
 where
     State: for<'a> Deserialize<'a> + Serialize + Clone,
     Event: for<'a> Deserialize<'a> + Serialize,
-    F: Fn(&Context<State, Event>, &mut ContractResult<State>),
+    F: ContractLogic<State, Event, M>,
 {
+    execute_contract_with(
+        state_ptr,
+        init_state_ptr,
+        event_ptr,
+        is_owner,
+        info_ptr,
+        &ExecutionOptions::default(),
+        callback,
+    )
+}
+
+/// Contract execution with custom options.
+///
+/// The state and the event are deserialized directly from the wire bytes (see `de`), and the
+/// final state is encoded directly (see `ser`), unless an option needs them as a
+/// `serde_json::Value`: schemas, migrations, a float policy other than `FloatPolicy::Allow`,
+/// `ResultMode::Patch` or canonical encoding for the final state.
+///
+/// A panic of the contract logic is reported to the host through `cout` and, if it unwinds,
+/// returned as a `codes::CONTRACT_PANIC` error.
+///
+/// # Arguments
+///
+/// * `state_ptr` - Pointer to the initial state of the contract.
+/// * `init_state_ptr` - Pointer to the init state, used if the state does not match `State`
+///   and the options select `StateFallback::InitState`.
+/// * `event_ptr` - Pointer to the event that triggered the contract execution.
+/// * `is_owner` - Is the sender of the event the owner of the contract?
+/// * `info_ptr` - Pointer to the execution information: signer, subject and governance
+///   identifiers, sequence number and timestamp.
+/// * `options` - Execution options.
+/// * `callback` - Contract logic, see `ContractLogic`.
+///
+/// # Returns
+///
+/// * `result_ptr` - Pointer to the contract execution result. In `ResultMode::Patch` the
+///   result carries a `StatePatch` over the state pointed by `state_ptr`.
+///
+pub fn execute_contract_with<F, M, State, Event>(
+    state_ptr: i32,
+    init_state_ptr: i32,
+    event_ptr: i32,
+    is_owner: i32,
+    info_ptr: i32,
+    options: &ExecutionOptions<State, Event>,
+    callback: F,
+) -> u32
+where
+    State: for<'a> Deserialize<'a> + Serialize + Clone,
+    Event: for<'a> Deserialize<'a> + Serialize,
+    F: ContractLogic<State, Event, M>,
+{
+    panic_hook::install();
     {
-        let error: String;
+        let error: ContractError;
         'process: {
-            let Ok(state_value) = deserialize(get_from_context(state_ptr)) else {
-                error = "Can not deserialize State".to_owned();
-                break 'process;
-            };
-            let state = match serde_json::from_value::<State>(state_value.0) {
-                Ok(state) => state,
-                Err(_) => {
-                    let Ok(init_state) = deserialize(get_from_context(init_state_ptr)) else {
-                        error = "Can not deserialize Init State".to_owned();
+            let (state, received_value) =
+                match receive_state(state_ptr, init_state_ptr, options) {
+                    Ok(received) => received,
+                    Err(state_error) => {
+                        error = state_error;
                         break 'process;
-                    };
-
-                    let Ok(init_state) = serde_json::from_value::<State>(init_state.0) else {
-                        error = "Can not convert State from value".to_owned();
-                        break 'process;
-                    };
-
-                    init_state
+                    }
+                };
+            let event = match receive_event(event_ptr, options) {
+                Ok(event) => event,
+                Err(event_error) => {
+                    error = event_error;
+                    break 'process;
                 }
             };
-            let Ok(event_value) = deserialize(get_from_context(event_ptr)) else {
-                error = "Can not deserialize Event".to_owned();
-                break 'process;
-            };
-            let Ok(event) = serde_json::from_value::<Event>(event_value.0) else {
-                error = "Can not convert Event from value".to_owned();
+            let Ok(info) = deserialize_info(get_from_context(info_ptr)) else {
+                error = ContractError::internal(
+                    codes::CONTEXT_DESERIALIZATION,
+                    "Can not deserialize execution context",
+                );
                 break 'process;
             };
-            let is_owner = if is_owner == 1 { true } else { false };
+            let is_owner = is_owner == 1;
             let context = Context {
                 initial_state: state.clone(),
                 event,
-                is_owner
+                is_owner,
+                signer: info.signer,
+                subject_id: info.subject_id,
+                governance_id: info.governance_id,
+                sn: info.sn,
+                timestamp: info.timestamp,
             };
             let mut contract_result = ContractResult::new(state);
-            callback(&context, &mut contract_result);
-            let Ok(state_value) = serde_json::to_value(&contract_result.final_state) else {
-                error = "Can not convert contract final state into Value".to_owned();
+            if let Err(panic_error) =
+                panic_hook::catch(|| callback.run(&context, &mut contract_result))
+            {
+                error = panic_error;
                 break 'process;
+            }
+            let final_state =
+                match final_state(&contract_result.final_state, contract_result.success, options)
+                {
+                    Ok(final_state) => final_state,
+                    Err(state_error) => {
+                        error = state_error;
+                        break 'process;
+                    }
+                };
+            let contract_error = execution_error(contract_result.success, contract_result.error);
+            let outputs = match contract_result.success {
+                true => contract_result.outputs,
+                false => Vec::new(),
             };
-            let result = ContractResultBorsh {
-                final_state: ValueWrapper(state_value),
-                success: contract_result.success,
-                error: format!("Error running contract event: {}", contract_result.error)
+            let result_ptr = match final_state {
+                // Only the changes over the state received from the host are returned.
+                FinalState::Value(final_value) if options.result_mode == ResultMode::Patch => {
+                    store(&ContractPatchResultBorsh {
+                        patch: patch::diff(&received_value.unwrap_or_default(), &final_value),
+                        success: contract_result.success,
+                        error: contract_error,
+                        outputs,
+                    })
+                }
+                final_state => store(&ContractEncodedResultBorsh {
+                    final_state,
+                    success: contract_result.success,
+                    error: contract_error,
+                    outputs,
+                }),
+            };
+            let Ok(result_ptr) = result_ptr else {
+                error = ContractError::internal(
+                    codes::RESULT_SERIALIZATION,
+                    "Can not return contract result",
+                );
+                break 'process;
+            };
+            return result_ptr;
+        };
+        match options.result_mode {
+            ResultMode::FullState => store(&ContractResultBorsh::error(error)),
+            ResultMode::Patch => store(&ContractPatchResultBorsh::error(error)),
+        }
+        .expect("Contract store process failed")
+    }
+}
+
+/// Batch of events execution.
+///
+/// The events are applied in order, each one over the final state of the previous one, so
+/// the state is received and returned once for the whole batch. Each event is checked like
+/// in `execute_contract_with`, and the float policy and migrations are applied to the final
+/// state of the batch. The `ResultMode` does not apply: the result always carries the whole
+/// final state.
+///
+/// The batch stops at the first event that fails. With `BatchMode::AllOrNothing` the final
+/// state is then the received state and the events before it are marked as not applied,
+/// without output events. With `BatchMode::StopAtFirstFailure` the final state is the state
+/// after the events before it.
+///
+/// # Arguments
+///
+/// * `state_ptr` - Pointer to the initial state of the contract.
+/// * `init_state_ptr` - Pointer to the init state, used if the state does not match `State`
+///   and the options select `StateFallback::InitState`.
+/// * `events_ptr` - Pointer to the array of events to apply.
+/// * `is_owner` - Is the sender of the events the owner of the contract?
+/// * `infos_ptr` - Pointer to the execution information of each event.
+/// * `options` - Execution options, including the `BatchMode`.
+/// * `callback` - Contract logic executed for each event, see `ContractLogic`.
+///
+/// # Returns
+///
+/// * `result_ptr` - Pointer to the batch execution result, with the result of each event run.
+///
+pub fn execute_contract_batch<F, M, State, Event>(
+    state_ptr: i32,
+    init_state_ptr: i32,
+    events_ptr: i32,
+    is_owner: i32,
+    infos_ptr: i32,
+    options: &ExecutionOptions<State, Event>,
+    callback: F,
+) -> u32
+where
+    State: for<'a> Deserialize<'a> + Serialize + Clone,
+    Event: for<'a> Deserialize<'a> + Serialize,
+    F: ContractLogic<State, Event, M>,
+{
+    panic_hook::install();
+    let mode = options.batch_mode;
+    {
+        let error: ContractError;
+        'process: {
+            let (received_state, _) = match receive_state(state_ptr, init_state_ptr, options) {
+                Ok(received) => received,
+                Err(state_error) => {
+                    error = state_error;
+                    break 'process;
+                }
+            };
+            let events_bytes = get_from_context(events_ptr);
+            let events = match de::array_elements(&events_bytes, options.decode_limits) {
+                Ok(events) => events,
+                Err(events_error) => {
+                    error = decode_error(
+                        events_error,
+                        codes::EVENT_DESERIALIZATION,
+                        "Can not deserialize Events",
+                    );
+                    break 'process;
+                }
+            };
+            let infos = match deserialize_infos(get_from_context(infos_ptr)) {
+                Ok(infos) if infos.len() == events.len() => infos,
+                _ => {
+                    error = ContractError::internal(
+                        codes::CONTEXT_DESERIALIZATION,
+                        "Can not deserialize the execution context of each event",
+                    );
+                    break 'process;
+                }
+            };
+            let is_owner = is_owner == 1;
+            let mut state = received_state.clone();
+            let mut results = Vec::with_capacity(events.len());
+            let mut failure = None;
+            for (event_bytes, info) in events.into_iter().zip(infos) {
+                match apply_event(&state, event_bytes, is_owner, info, options, &callback) {
+                    Ok((final_state, outputs)) => {
+                        state = final_state;
+                        results.push(EventResult {
+                            success: true,
+                            error: None,
+                            outputs,
+                        });
+                    }
+                    Err(event_error) => {
+                        results.push(EventResult {
+                            success: false,
+                            error: Some(event_error.clone()),
+                            outputs: Vec::new(),
+                        });
+                        failure = Some(event_error);
+                        break;
+                    }
+                }
+            }
+            let applied = match (&failure, mode) {
+                (Some(_), BatchMode::AllOrNothing) => 0,
+                (Some(_), BatchMode::StopAtFirstFailure) => results.len() - 1,
+                (None, _) => results.len(),
+            };
+            if applied == 0 {
+                state = received_state;
+                // The events run before the failed one are rolled back with it.
+                for result in &mut results {
+                    result.success = false;
+                    result.outputs.clear();
+                }
+            }
+            let final_state = match final_state(&state, applied > 0, options) {
+                Ok(final_state) => final_state,
+                Err(state_error) => {
+                    error = state_error;
+                    break 'process;
+                }
+            };
+            let result = ContractBatchResultBorsh {
+                final_state,
+                success: failure.is_none(),
+                error: failure,
+                mode,
+                events: results,
             };
-            // Después de haber sido modificado debemos guardar el nuevo estado.
-            // Sería interesante no tener que guardar estado si el evento no es modificante
             let Ok(result_ptr) = store(&result) else {
-                error = "Can not return contract result".to_owned();
+                error = ContractError::internal(
+                    codes::RESULT_SERIALIZATION,
+                    "Can not return contract batch result",
+                );
                 break 'process;
             };
             return result_ptr;
+        }
+        store(&ContractBatchResultBorsh::error(error, mode)).expect("Contract store process failed")
+    }
+}
+
+/// Read-only query over the contract state.
+///
+/// The query logic only borrows the state and the result carries no state, so a query can not
+/// change the subject. The state and the query are deserialized directly from the wire bytes,
+/// and the response is returned in the same wire format.
+///
+/// # Arguments
+///
+/// * `state_ptr` - Pointer to the state of the contract.
+/// * `query_ptr` - Pointer to the query.
+/// * `callback` - Query logic, `fn(&State, &Query) -> Result<Response, ContractError>`.
+///
+/// # Returns
+///
+/// * `result_ptr` - Pointer to the query result.
+///
+pub fn execute_query<F, State, Query, Response>(
+    state_ptr: i32,
+    query_ptr: i32,
+    callback: F,
+) -> u32
+where
+    State: for<'a> Deserialize<'a>,
+    Query: for<'a> Deserialize<'a>,
+    Response: Serialize,
+    F: Fn(&State, &Query) -> Result<Response, ContractError>,
+{
+    panic_hook::install();
+    {
+        let error: ContractError;
+        'process: {
+            let state = match de::from_bytes::<State>(&get_from_context(state_ptr)) {
+                Ok(state) => state,
+                Err(DecodeError::Message(_)) => {
+                    error = state_conversion_error();
+                    break 'process;
+                }
+                Err(state_error) => {
+                    error = decode_error(
+                        state_error,
+                        codes::STATE_DESERIALIZATION,
+                        "Can not deserialize State",
+                    );
+                    break 'process;
+                }
+            };
+            let query = match de::from_bytes::<Query>(&get_from_context(query_ptr)) {
+                Ok(query) => query,
+                Err(DecodeError::Message(_)) => {
+                    error = ContractError::validation(
+                        codes::QUERY_CONVERSION,
+                        "Can not convert Query from value",
+                    );
+                    break 'process;
+                }
+                Err(query_error) => {
+                    error = decode_error(
+                        query_error,
+                        codes::QUERY_DESERIALIZATION,
+                        "Can not deserialize Query",
+                    );
+                    break 'process;
+                }
+            };
+            let response = match panic_hook::catch(|| callback(&state, &query)) {
+                Ok(Ok(response)) => response,
+                Ok(Err(query_error)) | Err(query_error) => {
+                    error = query_error;
+                    break 'process;
+                }
+            };
+            let Ok(response) = ser::to_bytes(&response) else {
+                error = ContractError::internal(
+                    codes::RESPONSE_CONVERSION,
+                    "Can not encode query response",
+                );
+                break 'process;
+            };
+            let result = ContractQueryResultBorsh {
+                response: FinalState::Encoded(response),
+                success: true,
+                error: None,
+            };
+            let Ok(result_ptr) = store(&result) else {
+                error = ContractError::internal(
+                    codes::RESULT_SERIALIZATION,
+                    "Can not return query result",
+                );
+                break 'process;
+            };
+            return result_ptr;
+        }
+        store(&ContractQueryResultBorsh::error(error)).expect("Contract store process failed")
+    }
+}
+
+/// Runs the contract logic for one event of a batch and returns its final state and outputs.
+fn apply_event<F, M, State, Event>(
+    state: &State,
+    event_bytes: &[u8],
+    is_owner: bool,
+    info: ContextInfoBorsh,
+    options: &ExecutionOptions<State, Event>,
+    callback: &F,
+) -> Result<(State, Vec<ValueWrapper>), ContractError>
+where
+    State: for<'a> Deserialize<'a> + Serialize + Clone,
+    Event: for<'a> Deserialize<'a> + Serialize,
+    F: ContractLogic<State, Event, M>,
+{
+    let event = decode_event(event_bytes, options)?;
+    let context = Context {
+        initial_state: state.clone(),
+        event,
+        is_owner,
+        signer: info.signer,
+        subject_id: info.subject_id,
+        governance_id: info.governance_id,
+        sn: info.sn,
+        timestamp: info.timestamp,
+    };
+    let mut contract_result = ContractResult::new(state.clone());
+    panic_hook::catch(|| callback.run(&context, &mut contract_result))?;
+    if let Some(error) = execution_error(contract_result.success, contract_result.error) {
+        return Err(error);
+    }
+    options.check_state(None, &contract_result.final_state, codes::INVALID_FINAL_STATE)?;
+    Ok((contract_result.final_state, contract_result.outputs))
+}
+
+/// Final state to return to the host, checked if the contract reported success.
+///
+/// It is encoded directly, like the state was decoded, unless an option needs it as a `Value`.
+fn final_state<State, Event>(
+    state: &State,
+    success: bool,
+    options: &ExecutionOptions<State, Event>,
+) -> Result<FinalState, ContractError>
+where
+    State: Serialize,
+    Event: Serialize,
+{
+    if options.typed_state() && !options.canonical {
+        let mut serializer = ser::Serializer::new().with_dedicated_tags(options.dedicated_tags);
+        state.serialize(&mut serializer).map_err(|_| {
+            ContractError::internal(
+                codes::FINAL_STATE_CONVERSION,
+                "Can not encode contract final state",
+            )
+        })?;
+        let final_state = serializer.into_bytes();
+        if success {
+            options.check_state(None, state, codes::INVALID_FINAL_STATE)?;
+        }
+        return Ok(FinalState::Encoded(final_state));
+    }
+    if success {
+        return final_value(state, options).map(FinalState::Value);
+    }
+    let final_value = serde_json::to_value(state).map_err(|_| {
+        ContractError::internal(
+            codes::FINAL_STATE_CONVERSION,
+            "Can not convert contract final state into Value",
+        )
+    })?;
+    Ok(FinalState::Value(stored_value(final_value, options)))
+}
+
+/// Value of a state returned by the contract, after the float policy and the state checks,
+/// as stored by the host.
+fn final_value<State, Event>(
+    state: &State,
+    options: &ExecutionOptions<State, Event>,
+) -> Result<serde_json::Value, ContractError>
+where
+    State: Serialize,
+    Event: Serialize,
+{
+    let mut value = serde_json::to_value(state).map_err(|_| {
+        ContractError::internal(
+            codes::FINAL_STATE_CONVERSION,
+            "Can not convert contract final state into Value",
+        )
+    })?;
+    if let Some(normalized_state) = options.float_policy.apply(&value)? {
+        value = normalized_state;
+    }
+    options.check_state(Some(&value), state, codes::INVALID_FINAL_STATE)?;
+    Ok(stored_value(value, options))
+}
+
+/// Wraps a state value in the version envelope if migrations are enabled and canonicalizes it
+/// if requested.
+fn stored_value<State, Event>(
+    value: serde_json::Value,
+    options: &ExecutionOptions<State, Event>,
+) -> serde_json::Value {
+    let value = match &options.migrations {
+        Some(migrations) => migrations.wrap(value),
+        None => value,
+    };
+    if options.canonical {
+        ValueWrapper(value).canonical().0
+    } else {
+        value
+    }
+}
+
+/// State received from the host, with the value it was decoded from when the options need it.
+///
+/// Without options that work on the `Value` (schema, migrations, float policy or patches) the
+/// state is deserialized directly from the wire bytes.
+fn receive_state<State, Event>(
+    state_ptr: i32,
+    init_state_ptr: i32,
+    options: &ExecutionOptions<State, Event>,
+) -> Result<(State, Option<serde_json::Value>), ContractError>
+where
+    State: for<'a> Deserialize<'a> + Serialize,
+    Event: Serialize,
+{
+    let state_bytes = get_from_context(state_ptr);
+    if options.typed_state() {
+        let limits = options.decode_limits;
+        let state = match de::from_bytes_with_limits::<State>(&state_bytes, limits) {
+            Ok(state) => state,
+            Err(DecodeError::Message(message)) => {
+                if options.state_fallback == StateFallback::Reject {
+                    return Err(state_conversion_error());
+                }
+                report_state_fallback(&message);
+                let init_state_bytes = get_from_context(init_state_ptr);
+                match de::from_bytes_with_limits::<State>(&init_state_bytes, limits) {
+                    Ok(state) => state,
+                    Err(DecodeError::Message(_)) => return Err(state_conversion_error()),
+                    Err(error) => {
+                        return Err(decode_error(
+                            error,
+                            codes::INIT_STATE_DESERIALIZATION,
+                            "Can not deserialize Init State",
+                        ));
+                    }
+                }
+            }
+            Err(error) => {
+                return Err(decode_error(
+                    error,
+                    codes::STATE_DESERIALIZATION,
+                    "Can not deserialize State",
+                ));
+            }
         };
-        store(&ContractResultBorsh::error(&error)).expect("Contract store process failed")
+        options.check_state(None, &state, codes::INVALID_STATE)?;
+        return Ok((state, None));
+    }
+
+    let state_value = ValueWrapper::from_slice_with_limits(&state_bytes, options.decode_limits)
+        .map_err(|error| {
+            decode_error(error, codes::STATE_DESERIALIZATION, "Can not deserialize State")
+        })?;
+    let normalized_state = options.float_policy.apply(&state_value.0)?;
+    let received_value = normalized_state.as_ref().unwrap_or(&state_value.0);
+    let migrated_value;
+    let stored_value = match &options.migrations {
+        Some(migrations) => {
+            migrated_value = migrations.migrate(received_value.clone())?;
+            &migrated_value
+        }
+        None => received_value,
+    };
+    let state = match State::deserialize(stored_value) {
+        Ok(state) => {
+            options.check_state(Some(stored_value), &state, codes::INVALID_STATE)?;
+            state
+        }
+        Err(error) => {
+            if options.state_fallback == StateFallback::Reject {
+                return Err(state_conversion_error());
+            }
+            report_state_fallback(&error);
+            let init_state_bytes = get_from_context(init_state_ptr);
+            let init_state =
+                ValueWrapper::from_slice_with_limits(&init_state_bytes, options.decode_limits)
+                    .map_err(|error| {
+                        decode_error(
+                            error,
+                            codes::INIT_STATE_DESERIALIZATION,
+                            "Can not deserialize Init State",
+                        )
+                    })?;
+            let init_value = options.float_policy.apply(&init_state.0)?.unwrap_or(init_state.0);
+            let init_value = match &options.migrations {
+                Some(migrations) => migrations.migrate_init(init_value)?,
+                None => init_value,
+            };
+            let state =
+                State::deserialize(&init_value).map_err(|_| state_conversion_error())?;
+            options.check_state(Some(&init_value), &state, codes::INVALID_STATE)?;
+            state
+        }
+    };
+    Ok((state, Some(state_value.0)))
+}
+
+/// Reports through a log record that the received state is replaced by the init state, see
+/// `StateFallback::InitState`.
+fn report_state_fallback(reason: &dyn std::fmt::Display) {
+    crate::warn!(
+        "Received state can not be converted into State, using the init state";
+        reason = reason.to_string()
+    );
+}
+
+/// Event received from the host. Like the state, it is deserialized directly from the wire
+/// bytes when no option needs it as a `Value`.
+fn receive_event<State, Event>(
+    event_ptr: i32,
+    options: &ExecutionOptions<State, Event>,
+) -> Result<Event, ContractError>
+where
+    State: Serialize,
+    Event: for<'a> Deserialize<'a> + Serialize,
+{
+    decode_event(&get_from_context(event_ptr), options)
+}
+
+/// Event decoded from its wire bytes and checked with the options.
+fn decode_event<State, Event>(
+    event_bytes: &[u8],
+    options: &ExecutionOptions<State, Event>,
+) -> Result<Event, ContractError>
+where
+    State: Serialize,
+    Event: for<'a> Deserialize<'a> + Serialize,
+{
+    if options.typed_event() {
+        let event = de::from_bytes_with_limits::<Event>(event_bytes, options.decode_limits)
+            .map_err(|error| match error {
+                DecodeError::Message(_) => event_conversion_error(),
+                error => decode_error(
+                    error,
+                    codes::EVENT_DESERIALIZATION,
+                    "Can not deserialize Event",
+                ),
+            })?;
+        options.check_event(None, &event)?;
+        return Ok(event);
+    }
+
+    let event_value = ValueWrapper::from_slice_with_limits(event_bytes, options.decode_limits)
+        .map_err(|error| {
+            decode_error(error, codes::EVENT_DESERIALIZATION, "Can not deserialize Event")
+        })?;
+    let event_value = options.float_policy.apply(&event_value.0)?.unwrap_or(event_value.0);
+    let event = Event::deserialize(&event_value).map_err(|_| event_conversion_error())?;
+    options.check_event(Some(&event_value), &event)?;
+    Ok(event)
+}
+
+/// Error for a state or event that can not be decoded, with `codes::DECODE_LIMIT` if it
+/// exceeds the `DecodeLimits`.
+fn decode_error(error: DecodeError, code: u32, message: &str) -> ContractError {
+    if error.is_limit() {
+        ContractError::internal(codes::DECODE_LIMIT, format!("{}: {}", message, error))
+    } else {
+        ContractError::internal(code, message)
+    }
+}
+
+fn state_conversion_error() -> ContractError {
+    ContractError::validation(codes::STATE_CONVERSION, "Can not convert State from value")
+}
+
+fn event_conversion_error() -> ContractError {
+    ContractError::invalid_event(codes::EVENT_CONVERSION, "Can not convert Event from value")
+}
+
+/// Error reported to the host once the contract logic has run.
+fn execution_error(success: bool, error: Option<ContractError>) -> Option<ContractError> {
+    match (success, error) {
+        (true, _) => None,
+        (false, Some(error)) => Some(error),
+        (false, None) => Some(ContractError::internal(
+            codes::NOT_SUCCESSFUL,
+            "The contract did not report success",
+        )),
     }
 }
 
@@ -237,36 +1343,858 @@ fn deserialize(bytes: Vec<u8>) -> Result<ValueWrapper, Error> {
     BorshDeserialize::try_from_slice(&bytes).map_err(|e| Error::Deserialization(e.to_string()))
 }
 
+fn deserialize_info(bytes: Vec<u8>) -> Result<ContextInfoBorsh, Error> {
+    BorshDeserialize::try_from_slice(&bytes).map_err(|e| Error::Deserialization(e.to_string()))
+}
+
+fn deserialize_init_info(bytes: Vec<u8>) -> Result<InitInfoBorsh, Error> {
+    BorshDeserialize::try_from_slice(&bytes).map_err(|e| Error::Deserialization(e.to_string()))
+}
+
+fn deserialize_infos(bytes: Vec<u8>) -> Result<Vec<ContextInfoBorsh>, Error> {
+    BorshDeserialize::try_from_slice(&bytes).map_err(|e| Error::Deserialization(e.to_string()))
+}
+
 fn serialize<S: BorshSerialize>(data: S) -> Result<Vec<u8>, Error> {
     borsh::to_vec(&data).map_err(|e| Error::Serialization(e.to_string()))
 }
 
+#[cfg(feature = "legacy-abi")]
 fn get_from_context(pointer: i32) -> Vec<u8> {
-    let data = unsafe {
+    unsafe {
         let len = externf::pointer_len(pointer);
         let mut data = vec![];
         for i in 0..len {
             data.push(externf::read_byte(pointer + i));
         }
         data
+    }
+}
+
+#[cfg(not(feature = "legacy-abi"))]
+fn get_from_context(pointer: i32) -> Vec<u8> {
+    unsafe {
+        let len = externf::pointer_len(pointer);
+        let mut data = vec![0u8; len as usize];
+        externf::read_bytes(pointer, data.as_mut_ptr(), len);
+        data
+    }
+}
+
+/// Exports `main_function` with the signature expected by the host ABI and forwards the host
+/// pointers to `$execute`, `fn(state_ptr, init_state_ptr, event_ptr, is_owner, info_ptr)`.
+/// Used by `#[contract]`.
+#[cfg(not(feature = "legacy-abi"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! export_main_function {
+    ($execute:ident) => {
+        /// Contract entry point exported to the Kore wasm runtime.
+        ///
+        /// # Safety
+        ///
+        /// Pointers must reference values allocated by the host.
+        #[unsafe(no_mangle)]
+        pub unsafe fn main_function(
+            state_ptr: i32,
+            init_state_ptr: i32,
+            event_ptr: i32,
+            is_owner: i32,
+            info_ptr: i32,
+        ) -> u32 {
+            $execute(state_ptr, init_state_ptr, event_ptr, is_owner, info_ptr)
+        }
     };
-    data
+}
+
+/// Exports `main_function` with the signature expected by the host ABI and forwards the host
+/// pointers to `$execute`, `fn(state_ptr, init_state_ptr, event_ptr, is_owner, info_ptr)`.
+/// Used by `#[contract]`.
+///
+/// Legacy hosts do not send the execution information, so the contract gets the default one.
+#[cfg(feature = "legacy-abi")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! export_main_function {
+    ($execute:ident) => {
+        /// Contract entry point exported to the Kore wasm runtime.
+        ///
+        /// # Safety
+        ///
+        /// Pointers must reference values allocated by the host.
+        #[unsafe(no_mangle)]
+        pub unsafe fn main_function(
+            state_ptr: i32,
+            init_state_ptr: i32,
+            event_ptr: i32,
+            is_owner: i32,
+        ) -> u32 {
+            let info_ptr = $crate::default_info_ptr();
+            $execute(state_ptr, init_state_ptr, event_ptr, is_owner, info_ptr)
+        }
+    };
+}
+
+/// Pointer to the default execution information, for hosts that do not send it.
+#[cfg(feature = "legacy-abi")]
+#[doc(hidden)]
+pub fn default_info_ptr() -> i32 {
+    store(&ContextInfoBorsh::default()).expect("Contract store process failed") as i32
 }
 
 fn store<S>(data: &S) -> Result<u32, Error>
 where 
     S: BorshSerialize
 {
-    let bytes = serialize(&data).map_err(|e| Error::Serialization(e.to_string()))?;
+    let bytes = serialize(data).map_err(|e| Error::Serialization(e.to_string()))?;
+    Ok(store_bytes(&bytes))
+}
+
+/// Copies `bytes` into memory reserved in the host and returns its pointer.
+fn store_bytes(bytes: &[u8]) -> u32 {
     unsafe {
         let ptr = externf::alloc(bytes.len() as u32) as u32;
-        for (index, byte) in bytes.into_iter().enumerate() {
-            externf::write_byte(ptr, index as u32, byte);
+        #[cfg(feature = "legacy-abi")]
+        for (index, byte) in bytes.iter().enumerate() {
+            externf::write_byte(ptr, index as u32, *byte);
         }
-        Ok(ptr)
+        #[cfg(not(feature = "legacy-abi"))]
+        externf::write_bytes(ptr, bytes.as_ptr(), bytes.len() as u32);
+        ptr
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use crate::testing::{
+        BatchInvocation, Invocation, check_init, get, put_value, run_init, run_init_with,
+        run_query,
+    };
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    struct State {
+        value: u32,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    enum Event {
+        Set(u32),
+        Fail,
+    }
+
+    fn logic(context: &Context<State, Event>, result: &mut ContractResult<State>) {
+        if context.timestamp > 100 {
+            result.error = Some(ContractError::validation(codes::USER + 1, "Deadline exceeded"));
+            return;
+        }
+        match context.event {
+            Event::Set(value) if context.is_owner => {
+                result.final_state.value = value;
+                result.success = true;
+            }
+            Event::Set(_) => {
+                result.error = Some(ContractError::authorization(codes::USER, "Not the owner"))
+            }
+            Event::Fail => {}
+        }
+    }
+
+    #[test]
+    fn test_execute_contract() {
+        let result = Invocation::new(State { value: 1 }, Event::Set(2))
+            .owner(true)
+            .run(logic);
+        assert!(result.success);
+        assert_eq!(result.final_state, Some(State { value: 2 }));
+
+        let result = Invocation::new(State { value: 1 }, Event::Set(2)).run(logic);
+        assert!(!result.success);
+        let error = result.error.unwrap();
+        assert_eq!(error.code, codes::USER);
+        assert_eq!(error.category, ErrorCategory::Authorization);
+        assert_eq!(error.message, "Not the owner");
+
+        let result = Invocation::new(State { value: 1 }, Event::Fail).run(logic);
+        assert!(!result.success);
+        assert_eq!(result.error.unwrap().code, codes::NOT_SUCCESSFUL);
+    }
+
+    #[test]
+    fn test_execute_contract_context() {
+        let result = Invocation::new(State { value: 1 }, Event::Set(2))
+            .owner(true)
+            .signer("signer")
+            .subject_id("subject")
+            .governance_id("governance")
+            .sn(3)
+            .timestamp(101)
+            .run(|context: &Context<State, Event>, result: &mut ContractResult<State>| {
+                assert_eq!(context.signer, "signer");
+                assert_eq!(context.subject_id, "subject");
+                assert_eq!(context.governance_id, "governance");
+                assert_eq!(context.sn, 3);
+                logic(context, result)
+            });
+        assert!(!result.success);
+        assert_eq!(result.error.unwrap().code, codes::USER + 1);
+    }
+
+    #[test]
+    fn test_execute_contract_validation() {
+        let options = ExecutionOptions::default()
+            .state_schema(serde_json::json!({
+                "type": "object",
+                "properties": { "value": { "type": "integer", "maximum": 10 } },
+                "required": ["value"]
+            }))
+            .event_invariant(|event: &Event| match event {
+                Event::Set(0) => Err("Zero is not allowed".into()),
+                _ => Ok(()),
+            });
+        let run = |state: u32, event: Event| {
+            Invocation::new(State { value: state }, event)
+                .owner(true)
+                .options(options.clone())
+                .run(logic)
+        };
+        assert!(run(1, Event::Set(2)).success);
+
+        let error = run(11, Event::Set(2)).error.unwrap();
+        assert_eq!(error.code, codes::INVALID_STATE);
+        assert_eq!(error.details.unwrap().0["path"], "/value");
+
+        let error = run(1, Event::Set(0)).error.unwrap();
+        assert_eq!(error.code, codes::INVALID_EVENT);
+        assert_eq!(error.category, ErrorCategory::InvalidEvent);
+        assert_eq!(error.message, "Invalid event: Zero is not allowed");
+
+        let result = run(1, Event::Set(20));
+        assert!(!result.success);
+        assert_eq!(result.final_state, None);
+        assert_eq!(result.error.unwrap().code, codes::INVALID_FINAL_STATE);
+    }
+
+    #[test]
+    fn test_execute_contract_state_fallback() {
+        let state_ptr = testing::put_value(&"not a state");
+        let init_state_ptr = testing::put_value(&State { value: 7 });
+        let event_ptr = testing::put_value(&Event::Fail);
+        let info_ptr = testing::put(&borsh::to_vec(&ContextInfoBorsh::default()).unwrap());
+        // The execution fails unless the options fall back to the init state.
+        let options = ExecutionOptions::default();
+        let result_ptr = execute_contract_with(
+            state_ptr,
+            init_state_ptr,
+            event_ptr,
+            0,
+            info_ptr,
+            &options,
+            logic,
+        );
+        let result = ContractResultBorsh::try_from_slice(&testing::get(result_ptr)).unwrap();
+        assert_eq!(result.error.unwrap().code, codes::STATE_CONVERSION);
+    }
+
+    #[test]
+    fn test_execute_contract_migrations() {
+        fn rename_amount(mut state: serde_json::Value) -> Result<serde_json::Value, ContractError> {
+            state["value"] = state["amount"].take();
+            Ok(state)
+        }
+        let options =
+            ExecutionOptions::default().migrations(Migrations::new(1).migration(0, rename_amount));
+        let state_ptr = testing::put_value(&serde_json::json!({ "amount": 4 }));
+        let init_state_ptr = testing::put_value(&State { value: 0 });
+        let event_ptr = testing::put_value(&Event::Set(5));
+        let info_ptr = testing::put(&borsh::to_vec(&ContextInfoBorsh::default()).unwrap());
+        let result_ptr = execute_contract_with(
+            state_ptr,
+            init_state_ptr,
+            event_ptr,
+            1,
+            info_ptr,
+            &options,
+            |context: &Context<State, Event>, result: &mut ContractResult<State>| {
+                assert_eq!(context.initial_state.value, 4);
+                logic(context, result)
+            },
+        );
+        let result = ContractResultBorsh::try_from_slice(&testing::get(result_ptr)).unwrap();
+        assert!(result.success);
+        assert_eq!(result.final_state.0, migration::wrap(1, serde_json::json!({ "value": 5 })));
+
+        let result = Invocation::new(State { value: 1 }, Event::Set(2))
+            .owner(true)
+            .version(1)
+            .options(options.clone())
+            .run(logic);
+        assert_eq!(result.final_state, Some(State { value: 2 }));
+
+        // The constructor stores the initial state with the current version, so the first
+        // event does not migrate it again.
+        let init = |context: &InitContext<State>| Ok(context.init_state.clone());
+        let result = run_init_with(&State { value: 3 }, "alice", "Jsubject", &options, init);
+        assert_eq!(result.init_state, Some(State { value: 3 }));
+        let init_state_ptr = testing::put_value(&State { value: 3 });
+        let info = InitInfoBorsh {
+            creator: "alice".to_owned(),
+            subject_id: "Jsubject".to_owned(),
+        };
+        let info_ptr = testing::put(&borsh::to_vec(&info).unwrap());
+        let result_ptr = init_contract_with(init_state_ptr, info_ptr, &options, init);
+        let result =
+            ContractInitResultBorsh::<ValueWrapper>::try_from_slice(&testing::get(result_ptr))
+                .unwrap();
+        assert_eq!(result.init_state.0, migration::wrap(1, serde_json::json!({ "value": 3 })));
+        let state_ptr = testing::put(&borsh::to_vec(&result.init_state).unwrap());
+        let event_ptr = testing::put_value(&Event::Set(4));
+        let info_ptr = testing::put(&borsh::to_vec(&ContextInfoBorsh::default()).unwrap());
+        let result_ptr = execute_contract_with(
+            state_ptr,
+            init_state_ptr,
+            event_ptr,
+            1,
+            info_ptr,
+            &options,
+            logic,
+        );
+        let result = ContractResultBorsh::try_from_slice(&testing::get(result_ptr)).unwrap();
+        assert!(result.success);
+        assert_eq!(result.final_state.0, migration::wrap(1, serde_json::json!({ "value": 4 })));
+    }
+
+    #[test]
+    fn test_execute_contract_float_policy() {
+        let run = |policy: FloatPolicy, state: serde_json::Value| {
+            let state_ptr = testing::put_value(&state);
+            let event_ptr = testing::put_value(&Event::Set(5));
+            let info_ptr = testing::put(&borsh::to_vec(&ContextInfoBorsh::default()).unwrap());
+            let result_ptr = execute_contract_with(
+                state_ptr,
+                state_ptr,
+                event_ptr,
+                1,
+                info_ptr,
+                &ExecutionOptions::default().float_policy(policy),
+                logic,
+            );
+            ContractResultBorsh::try_from_slice(&testing::get(result_ptr)).unwrap()
+        };
+        // `1.0` can not be converted into `u32` unless it is normalized.
+        let result = run(FloatPolicy::Allow, serde_json::json!({ "value": 1.0 }));
+        assert_eq!(result.error.unwrap().code, codes::STATE_CONVERSION);
+        let result = run(FloatPolicy::Normalize, serde_json::json!({ "value": 1.0 }));
+        assert!(result.success);
+        let result = run(FloatPolicy::Normalize, serde_json::json!({ "value": 1.5 }));
+        assert_eq!(result.error.unwrap().code, codes::FLOAT_NOT_ALLOWED);
+        let result = run(FloatPolicy::Reject, serde_json::json!({ "value": 1.0 }));
+        let error = result.error.unwrap();
+        assert_eq!(error.code, codes::FLOAT_NOT_ALLOWED);
+        assert_eq!(error.message, "Floating-point number 1.0 at '/value' is not allowed");
+
+        let state_ptr = testing::put_value(&serde_json::json!({ "value": 2.0 }));
+        let result_ptr = check_init_data_with(
+            state_ptr,
+            FloatPolicy::Reject,
+            |_: &State, result: &mut ContractInitCheck| result.success = true,
+        );
+        let result = ContractInitCheckBorsh::try_from_slice(&testing::get(result_ptr)).unwrap();
+        assert_eq!(result.error.unwrap().code, codes::FLOAT_NOT_ALLOWED);
+    }
+
+    #[test]
+    fn test_execute_contract_typed() {
+        let run = |state_ptr: i32, init_state_ptr: i32, event_ptr: i32, fallback| {
+            let info_ptr = testing::put(&borsh::to_vec(&ContextInfoBorsh::default()).unwrap());
+            let options = ExecutionOptions::default().state_fallback(fallback);
+            let result_ptr = execute_contract_with(
+                state_ptr,
+                init_state_ptr,
+                event_ptr,
+                1,
+                info_ptr,
+                &options,
+                logic,
+            );
+            ContractResultBorsh::try_from_slice(&testing::get(result_ptr)).unwrap()
+        };
+        let init_state_ptr = testing::put_value(&State { value: 3 });
+        let event_ptr = testing::put_value(&Event::Set(5));
+
+        let state_ptr = testing::put_value(&State { value: 1 });
+        let result = run(state_ptr, init_state_ptr, event_ptr, StateFallback::Reject);
+        assert!(result.success);
+        assert_eq!(result.final_state.0, serde_json::json!({ "value": 5 }));
+
+        // A state that does not match `State` is rejected, unless the options fall back to the
+        // init state.
+        let state_ptr = testing::put_value(&serde_json::json!({ "value": "1" }));
+        let event_ptr = testing::put_value(&Event::Fail);
+        let result = run(state_ptr, init_state_ptr, event_ptr, StateFallback::Reject);
+        assert_eq!(result.error.unwrap().code, codes::STATE_CONVERSION);
+        let result = run(state_ptr, init_state_ptr, event_ptr, StateFallback::InitState);
+        assert_eq!(result.final_state.0, serde_json::json!({ "value": 3 }));
+
+        let event_ptr = testing::put_value(&Event::Set(5));
+        let result = run(testing::put(&[7]), init_state_ptr, event_ptr, StateFallback::InitState);
+        assert_eq!(result.error.unwrap().code, codes::STATE_DESERIALIZATION);
+        let event_ptr = testing::put_value(&serde_json::json!({ "Unknown": 1 }));
+        let result = run(init_state_ptr, init_state_ptr, event_ptr, StateFallback::Reject);
+        assert_eq!(result.error.unwrap().code, codes::EVENT_CONVERSION);
+
+        let limits = DecodeLimits {
+            max_depth: 0,
+            ..DecodeLimits::default()
+        };
+        let result = Invocation::new(State { value: 1 }, Event::Set(2))
+            .options(ExecutionOptions::default().decode_limits(limits))
+            .run(logic);
+        let error = result.error.unwrap();
+        assert_eq!(error.code, codes::DECODE_LIMIT);
+        let message = "Can not deserialize State: Nesting depth exceeds the limit of 0";
+        assert_eq!(error.message, message);
+    }
+
+    #[test]
+    fn test_execute_contract_big_integers() {
+        #[derive(Clone, Serialize, Deserialize)]
+        struct Supply {
+            total: u128,
+        }
+
+        fn mint(context: &Context<Supply, u128>, result: &mut ContractResult<Supply>) {
+            result.final_state.total += context.event;
+            result.success = true;
+        }
+        let run = |options: &ExecutionOptions<Supply, u128>| {
+            let state_ptr = testing::put_value(&Supply { total: u128::from(u64::MAX) + 1 });
+            let event_ptr = testing::put_value(&u128::from(u64::MAX));
+            let info_ptr = testing::put(&borsh::to_vec(&ContextInfoBorsh::default()).unwrap());
+            let result_ptr =
+                execute_contract_with(state_ptr, state_ptr, event_ptr, 1, info_ptr, options, mint);
+            ContractResultBorsh::try_from_slice(&testing::get(result_ptr)).unwrap()
+        };
+        // The default options decode the state straight into `Supply`, the float policy
+        // needs its `Value` first.
+        let total = serde_json::json!({ "total": u128::from(u64::MAX) * 2 + 1 });
+        for options in [
+            ExecutionOptions::default(),
+            ExecutionOptions::default().float_policy(FloatPolicy::Reject),
+        ] {
+            let result = run(&options);
+            assert!(result.success, "{:?}", result.error);
+            assert_eq!(result.final_state.0, total);
+        }
+    }
+
+    #[test]
+    fn test_execute_contract_bytes() {
+        #[derive(Clone, Serialize, Deserialize)]
+        struct Document {
+            content: Bytes,
+        }
+
+        fn logic(context: &Context<Document, Bytes>, result: &mut ContractResult<Document>) {
+            result.final_state.content = context.event.clone();
+            result.success = true;
+        }
+
+        // Only a final state encoded straight from `Document` has the bytes tag, on request.
+        for (dedicated_tags, canonical, tagged) in
+            [(false, false, false), (true, false, true), (true, true, false)]
+        {
+            let state_ptr = testing::put_value(&Document {
+                content: Bytes::default(),
+            });
+            let event_ptr = testing::put_value(&Bytes(vec![1, 2]));
+            let info_ptr = testing::put(&borsh::to_vec(&ContextInfoBorsh::default()).unwrap());
+            let options = ExecutionOptions::default()
+                .dedicated_tags(dedicated_tags)
+                .canonical(canonical);
+            let result_ptr = execute_contract_with(
+                state_ptr, state_ptr, event_ptr, 1, info_ptr, &options, logic,
+            );
+            let bytes = testing::get(result_ptr);
+            let raw = bytes.windows(7).any(|window| window == [6, 2, 0, 0, 0, 1, 2]);
+            assert_eq!(raw, tagged);
+            let result = ContractResultBorsh::try_from_slice(&bytes).unwrap();
+            let expected = serde_json::json!({ "content": { "$bytes": "AQI=" } });
+            assert_eq!(result.final_state.0, expected);
+        }
+    }
+
+    #[test]
+    fn test_execute_contract_serde_bytes() {
+        #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+        struct Document {
+            #[serde(with = "serde_bytes")]
+            content: Vec<u8>,
+        }
+
+        fn logic(context: &Context<Document, u8>, result: &mut ContractResult<Document>) {
+            result.final_state.content.push(context.event);
+            result.success = true;
+        }
+
+        let expected = Some(Document { content: vec![1, 2] });
+        let state = Document { content: vec![1] };
+        let result = Invocation::new(state.clone(), 2).run(logic);
+        assert_eq!(result.final_state, expected);
+        let stored = result.final_state.unwrap();
+
+        // The state encoded by the typed path is read back through its `Value`.
+        let options = ExecutionOptions::default().float_policy(FloatPolicy::Reject);
+        let result = Invocation::new(stored, 3).options(options).run(logic);
+        assert!(result.success);
+        assert_eq!(result.final_state, Some(Document { content: vec![1, 2, 3] }));
+        assert_eq!(
+            to_bytes(&state).unwrap(),
+            borsh::to_vec(&ValueWrapper(serde_json::json!({ "content": [1] }))).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_execute_contract_batch() {
+        let result = BatchInvocation::new(State { value: 1 }, vec![Event::Set(2), Event::Set(3)])
+            .owner(true)
+            .run(logic);
+        assert!(result.success);
+        assert_eq!(result.final_state, Some(State { value: 3 }));
+        assert_eq!(result.mode, BatchMode::AllOrNothing);
+        assert_eq!(result.events.len(), 2);
+        assert!(result.events.iter().all(|event| event.success));
+
+        let events = vec![Event::Set(2), Event::Fail, Event::Set(4)];
+        let result = BatchInvocation::new(State { value: 1 }, events)
+            .owner(true)
+            .run(logic);
+        assert!(!result.success);
+        assert_eq!(result.final_state, Some(State { value: 1 }));
+        assert_eq!(result.events.len(), 2);
+        assert!(!result.events[0].success);
+        assert_eq!(result.events[0].error, None);
+        assert_eq!(result.events[1].error.as_ref().unwrap().code, codes::NOT_SUCCESSFUL);
+        assert_eq!(result.error, result.events[1].error);
+
+        let events = vec![Event::Set(2), Event::Set(20), Event::Set(4)];
+        let options = ExecutionOptions::default()
+            .batch_mode(BatchMode::StopAtFirstFailure)
+            .state_invariant(|state: &State| match state.value {
+                0..=10 => Ok(()),
+                _ => Err(ContractError::validation(codes::USER, "Value too large")),
+            });
+        let result = BatchInvocation::new(State { value: 1 }, events)
+            .owner(true)
+            .options(options)
+            .run(logic);
+        assert!(!result.success);
+        assert_eq!(result.mode, BatchMode::StopAtFirstFailure);
+        assert_eq!(result.final_state, Some(State { value: 2 }));
+        assert_eq!(result.error.unwrap().code, codes::INVALID_FINAL_STATE);
+
+        let result = BatchInvocation::new(State { value: 0 }, vec![Event::Fail, Event::Fail])
+            .sn(5)
+            .subject_id("Jsubject")
+            .governance_id("Jgovernance")
+            .run(|context: &Context<State, Event>, result: &mut ContractResult<State>| {
+                assert_eq!(context.initial_state.value as u64 + 5, context.sn);
+                assert_eq!(
+                    (context.subject_id.as_str(), context.governance_id.as_str()),
+                    ("Jsubject", "Jgovernance")
+                );
+                result.final_state.value += 1;
+                result.success = true;
+            });
+        assert_eq!(result.final_state, Some(State { value: 2 }));
+    }
+
+    #[test]
+    fn test_execute_contract_patch() {
+        let options = ExecutionOptions::default().result_mode(ResultMode::Patch);
+        let result = Invocation::new(State { value: 1 }, Event::Set(2))
+            .owner(true)
+            .options(options.clone())
+            .run(logic);
+        assert!(result.success);
+        assert_eq!(result.final_state, Some(State { value: 2 }));
+        assert_eq!(
+            result.patch,
+            Some(StatePatch::Patch(vec![patch::PatchOperation::Replace {
+                path: "/value".to_owned(),
+                value: ValueWrapper(serde_json::json!(2)),
+            }]))
+        );
+
+        let result = Invocation::new(State { value: 1 }, Event::Set(1))
+            .owner(true)
+            .options(options)
+            .run(logic);
+        assert_eq!(result.patch, Some(StatePatch::Unchanged));
+        assert_eq!(result.final_state, Some(State { value: 1 }));
+    }
+
+    #[test]
+    fn test_export_main_function() {
+        fn execute(
+            state_ptr: i32,
+            init_state_ptr: i32,
+            event_ptr: i32,
+            is_owner: i32,
+            info_ptr: i32,
+        ) -> u32 {
+            execute_contract(state_ptr, init_state_ptr, event_ptr, is_owner, info_ptr, logic)
+        }
+        crate::export_main_function!(execute);
+
+        // Legacy hosts do not send the timestamp, so the deadline of `logic` is never exceeded.
+        let state_ptr = testing::put_value(&State { value: 1 });
+        let event_ptr = testing::put_value(&Event::Set(2));
+        #[cfg(not(feature = "legacy-abi"))]
+        let result_ptr = unsafe {
+            let info = ContextInfoBorsh {
+                timestamp: 101,
+                ..Default::default()
+            };
+            let info_ptr = testing::put(&borsh::to_vec(&info).unwrap());
+            main_function(state_ptr, state_ptr, event_ptr, 1, info_ptr)
+        };
+        #[cfg(feature = "legacy-abi")]
+        let result_ptr = unsafe { main_function(state_ptr, state_ptr, event_ptr, 1) };
+        let result = ContractResultBorsh::try_from_slice(&testing::get(result_ptr)).unwrap();
+        assert_eq!(result.success, cfg!(feature = "legacy-abi"));
+    }
+
+    #[test]
+    fn test_execute_contract_invalid_state() {
+        let state_ptr = testing::put_value(&"not a state");
+        let init_state_ptr = testing::put_value(&State { value: 7 });
+        let event_ptr = testing::put_value(&Event::Fail);
+        let info_ptr = testing::put(&borsh::to_vec(&ContextInfoBorsh::default()).unwrap());
+        let options = ExecutionOptions::default().state_fallback(StateFallback::InitState);
+        let (result_ptr, lines) = testing::capture_output(|| {
+            execute_contract_with(
+                state_ptr,
+                init_state_ptr,
+                event_ptr,
+                0,
+                info_ptr,
+                &options,
+                logic,
+            )
+        });
+        let result = ContractResultBorsh::try_from_slice(&testing::get(result_ptr)).unwrap();
+        assert_eq!(result.final_state, ValueWrapper(serde_json::json!({ "value": 7 })));
+        let fallback = "WARN kore_contract_sdk: Received state can not be converted into State, \
+                        using the init state reason=";
+        if log::enabled(log::Level::Warn) {
+            assert_eq!(lines.len(), 1);
+            assert!(lines[0].starts_with(fallback));
+        } else {
+            assert!(lines.is_empty());
+        }
+
+        let options = options.float_policy(FloatPolicy::Reject);
+        let (result_ptr, lines) = testing::capture_output(|| {
+            execute_contract_with(
+                state_ptr,
+                init_state_ptr,
+                event_ptr,
+                0,
+                info_ptr,
+                &options,
+                logic,
+            )
+        });
+        let result = ContractResultBorsh::try_from_slice(&testing::get(result_ptr)).unwrap();
+        assert_eq!(result.final_state, ValueWrapper(serde_json::json!({ "value": 7 })));
+        assert_eq!(lines.len(), log::enabled(log::Level::Warn) as usize);
+
+        let event_ptr = testing::put_value(&"not an event");
+        let result_ptr =
+            execute_contract(init_state_ptr, init_state_ptr, event_ptr, 0, info_ptr, logic);
+        let result = ContractResultBorsh::try_from_slice(&testing::get(result_ptr)).unwrap();
+        assert!(!result.success);
+        let error = result.error.unwrap();
+        assert_eq!(error.code, codes::EVENT_CONVERSION);
+        assert_eq!(error.category, ErrorCategory::InvalidEvent);
+    }
+
+    #[test]
+    fn test_contract_outputs() {
+        fn emitting(context: &Context<State, Event>, result: &mut ContractResult<State>) {
+            let previous = result.final_state.value;
+            result.emit(&serde_json::json!({ "received": context.sn })).unwrap();
+            match context.event {
+                Event::Set(value) => {
+                    result.final_state.value = value;
+                    result.emit(&("changed", previous, value)).unwrap();
+                    result.success = true;
+                }
+                Event::Fail => {}
+            }
+        }
+
+        let result = Invocation::new(State { value: 1 }, Event::Set(2)).sn(3).run(emitting);
+        assert!(result.success);
+        // The version 1 result layout has no output events.
+        let outputs = match cfg!(feature = "legacy-abi") {
+            true => Vec::new(),
+            false => vec![
+                serde_json::json!({ "received": 3 }),
+                serde_json::json!(["changed", 1, 2]),
+            ],
+        };
+        assert_eq!(result.outputs, outputs);
+
+        let result = Invocation::new(State { value: 1 }, Event::Fail).run(emitting);
+        assert!(!result.success);
+        assert!(result.outputs.is_empty());
+
+        let result = Invocation::new(State { value: 1 }, Event::Set(2))
+            .options(ExecutionOptions::default().result_mode(ResultMode::Patch))
+            .run(emitting);
+        assert_eq!(result.outputs.len(), 2);
+
+        let events = vec![Event::Set(2), Event::Fail];
+        let options = ExecutionOptions::default().batch_mode(BatchMode::StopAtFirstFailure);
+        let result = BatchInvocation::new(State { value: 1 }, events)
+            .options(options)
+            .run(emitting);
+        assert!(result.events[0].success);
+        assert_eq!(result.events[0].outputs.len(), 2);
+        assert!(result.events[1].outputs.is_empty());
+
+        // Rolled back events do not emit their outputs.
+        let events = vec![Event::Set(2), Event::Fail];
+        let result = BatchInvocation::new(State { value: 1 }, events).run(emitting);
+        assert_eq!(result.final_state, Some(State { value: 1 }));
+        assert!(result.events.iter().all(|event| !event.success && event.outputs.is_empty()));
+    }
+
+    #[test]
+    fn test_result_layout() {
+        let error = ContractError::authorization(codes::USER, "Not the owner");
+        let bytes = borsh::to_vec(&ContractResultBorsh::error(error.clone())).unwrap();
+        #[cfg(feature = "legacy-abi")]
+        {
+            let (final_state, success, message) =
+                <(ValueWrapper, bool, String)>::try_from_slice(&bytes).unwrap();
+            assert_eq!((final_state.0, success), (serde_json::Value::Null, false));
+            assert_eq!(serde_json::from_str::<ContractError>(&message).unwrap(), error);
+            let bytes = borsh::to_vec(&(true, String::new())).unwrap();
+            assert_eq!(ContractInitCheckBorsh::try_from_slice(&bytes).unwrap().error, None);
+            let bytes = borsh::to_vec(&(false, "Invalid state".to_owned())).unwrap();
+            let result = ContractInitCheckBorsh::try_from_slice(&bytes).unwrap();
+            assert_eq!(result.error, Some(ContractError::from("Invalid state")));
+        }
+        #[cfg(not(feature = "legacy-abi"))]
+        {
+            type Layout = (ValueWrapper, bool, Option<ContractError>, Vec<ValueWrapper>);
+            let (final_state, success, result_error, outputs) =
+                Layout::try_from_slice(&bytes).unwrap();
+            assert_eq!((final_state.0, success), (serde_json::Value::Null, false));
+            assert_eq!((result_error, outputs), (Some(error), Vec::new()));
+            let bytes = borsh::to_vec(&ContractInitCheckBorsh::ok()).unwrap();
+            assert_eq!(bytes, borsh::to_vec(&(true, None::<ContractError>)).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_execute_query() {
+        #[derive(Serialize, Deserialize)]
+        enum Query {
+            Doubled,
+            Divided(u32),
+        }
+
+        fn query(state: &State, query: &Query) -> Result<u32, ContractError> {
+            match query {
+                Query::Doubled => Ok(state.value * 2),
+                Query::Divided(divisor) => state
+                    .value
+                    .checked_div(*divisor)
+                    .ok_or_else(|| ContractError::validation(codes::USER, "Division by zero")),
+            }
+        }
+
+        let result = run_query(&State { value: 6 }, &Query::Doubled, query);
+        assert!(result.success);
+        assert_eq!(result.response, Some(12));
+
+        let result = run_query(&State { value: 6 }, &Query::Divided(0), query);
+        assert!(!result.success);
+        assert_eq!(result.response, None);
+        assert_eq!(result.error.unwrap().code, codes::USER);
+
+        let result_ptr = execute_query(put_value(&State { value: 6 }), put_value(&true), query);
+        let result = ContractQueryResultBorsh::<ValueWrapper>::try_from_slice(&get(result_ptr));
+        assert_eq!(result.unwrap().error.unwrap().code, codes::QUERY_CONVERSION);
+    }
+
+    #[test]
+    fn test_contract_panic() {
+        fn checked(context: &Context<State, Event>) -> Result<State, ContractError> {
+            let value = match context.event {
+                Event::Set(value) => value,
+                Event::Fail => panic!("No value to set"),
+            };
+            Ok(State { value: context.initial_state.value.checked_add(value).unwrap() })
+        }
+
+        let result = Invocation::new(State { value: 1 }, Event::Fail).run(checked);
+        assert!(!result.success);
+        let error = result.error.unwrap();
+        assert_eq!((error.code, error.category), (codes::CONTRACT_PANIC, ErrorCategory::Internal));
+        assert!(error.message.starts_with("Contract panicked at src/lib.rs:"));
+        assert!(error.message.ends_with(": No value to set"));
+
+        let events = vec![Event::Set(1), Event::Set(u32::MAX)];
+        let result = BatchInvocation::new(State { value: 1 }, events).run(checked);
+        assert!(!result.success);
+        assert!(!result.events[0].success);
+        assert_eq!(result.error.unwrap().code, codes::CONTRACT_PANIC);
+
+        let result = check_init(&State { value: 0 }, |state: &State| {
+            assert!(state.value > 0, "Value must be positive");
+            Ok(())
+        });
+        assert_eq!(result.error.unwrap().code, codes::CONTRACT_PANIC);
+    }
+
+    #[test]
+    fn test_init_contract() {
+        fn init(context: &InitContext<State>) -> Result<State, ContractError> {
+            let subject_id = &context.subject_id;
+            ensure!(subject_id.starts_with('J'), "Unexpected subject {}", subject_id);
+            match context.init_state.value {
+                0 => Ok(State { value: context.creator.len() as u32 }),
+                value => Ok(State { value }),
+            }
+        }
+
+        let result = run_init(&State { value: 0 }, "alice", "Jsubject", init);
+        assert!(result.success);
+        assert_eq!(result.init_state, Some(State { value: 5 }));
+        let result = run_init(&State { value: 7 }, "alice", "Jsubject", init);
+        assert_eq!(result.init_state, Some(State { value: 7 }));
+
+        let result = run_init(&State { value: 0 }, "alice", "subject", init);
+        assert!(!result.success);
+        assert_eq!(result.init_state, None);
+        assert_eq!(result.error.unwrap().message, "Unexpected subject subject");
+    }
+
+    #[test]
+    fn test_check_init_data() {
+        let check = |state: &State, result: &mut ContractInitCheck| {
+            result.success = state.value > 0;
+            result.error = Some("Value must be positive".into());
+        };
+        let result = check_init(&State { value: 0 }, check);
+        assert!(!result.success);
+        let error = result.error.unwrap();
+        assert_eq!(error.code, codes::UNSPECIFIED);
+        assert_eq!(error.message, "Value must be positive");
+    }
 }
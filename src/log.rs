@@ -0,0 +1,199 @@
+// Copyright 2025 Kore Ledger
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Contract logging through the `cout` host function.
+//!
+//! The `error!`, `warn!`, `info!`, `debug!` and `trace!` macros take a format string and its
+//! arguments, optionally followed by `;` and `key = value` fields, formatted with `Debug`:
+//!
+//! ```ignore
+//! info!("Deposit accepted"; signer = context.signer, amount = amount);
+//! warn!("Balance below {}", threshold; balance = state.balance);
+//! ```
+//!
+//! Each record is written to the host as one line, `LEVEL target: message key=value ...`,
+//! where the target is the module path of the call. The native test host prints the lines to
+//! stderr, or keeps them when run inside `testing::capture_output`.
+//!
+//! Records above `MAX_LEVEL` are removed at compile time. It is selected with the features
+//! `max-level-off`, `max-level-error`, `max-level-warn`, `max-level-info`, `max-level-debug`
+//! and `max-level-trace`, the most restrictive one wins. Without any of them every level is
+//! logged.
+
+use std::fmt::{self, Write};
+
+use crate::{externf, store_bytes};
+
+/// Severity of a log record, from the most to the least severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    /// The contract can not go on.
+    Error,
+    /// Unexpected situation the contract can deal with.
+    Warn,
+    /// Relevant step of the contract logic.
+    Info,
+    /// Detail useful when debugging the contract.
+    Debug,
+    /// Very verbose detail.
+    Trace,
+}
+
+impl Level {
+    /// Upper case name of the level, as written in log records.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Least severe level logged, `None` if logging is disabled.
+pub const MAX_LEVEL: Option<Level> = if cfg!(feature = "max-level-off") {
+    None
+} else if cfg!(feature = "max-level-error") {
+    Some(Level::Error)
+} else if cfg!(feature = "max-level-warn") {
+    Some(Level::Warn)
+} else if cfg!(feature = "max-level-info") {
+    Some(Level::Info)
+} else if cfg!(feature = "max-level-debug") {
+    Some(Level::Debug)
+} else {
+    Some(Level::Trace)
+};
+
+/// Whether records of `level` are logged.
+pub const fn enabled(level: Level) -> bool {
+    match MAX_LEVEL {
+        Some(max_level) => level as u8 <= max_level as u8,
+        None => false,
+    }
+}
+
+/// Writes a record to the host. Used by the logging macros.
+#[doc(hidden)]
+pub fn write_record(
+    level: Level,
+    target: &str,
+    message: fmt::Arguments,
+    fields: &[(&str, &dyn fmt::Debug)],
+) {
+    let mut line = format!("{} {}: {}", level, target, message);
+    for (key, value) in fields {
+        let _ = write!(line, " {}={:?}", key, value);
+    }
+    unsafe { externf::cout(store_bytes(line.as_bytes())) };
+}
+
+/// Logs a record at a given `Level`, see the `log` module.
+#[macro_export]
+macro_rules! log {
+    (
+        $level:expr,
+        $format:literal $(, $argument:expr)* $(,)?
+        $(; $($key:ident = $value:expr),+ $(,)?)?
+    ) => {{
+        let level: $crate::log::Level = $level;
+        if $crate::log::enabled(level) {
+            $crate::log::write_record(
+                level,
+                ::core::module_path!(),
+                ::core::format_args!($format $(, $argument)*),
+                &[$($((::core::stringify!($key), &$value as &dyn ::core::fmt::Debug)),+)?],
+            );
+        }
+    }};
+}
+
+/// Logs a record at `Level::Error`, see the `log` module.
+#[macro_export]
+macro_rules! error {
+    ($($record:tt)+) => {
+        $crate::log!($crate::log::Level::Error, $($record)+)
+    };
+}
+
+/// Logs a record at `Level::Warn`, see the `log` module.
+#[macro_export]
+macro_rules! warn {
+    ($($record:tt)+) => {
+        $crate::log!($crate::log::Level::Warn, $($record)+)
+    };
+}
+
+/// Logs a record at `Level::Info`, see the `log` module.
+#[macro_export]
+macro_rules! info {
+    ($($record:tt)+) => {
+        $crate::log!($crate::log::Level::Info, $($record)+)
+    };
+}
+
+/// Logs a record at `Level::Debug`, see the `log` module.
+#[macro_export]
+macro_rules! debug {
+    ($($record:tt)+) => {
+        $crate::log!($crate::log::Level::Debug, $($record)+)
+    };
+}
+
+/// Logs a record at `Level::Trace`, see the `log` module.
+#[macro_export]
+macro_rules! trace {
+    ($($record:tt)+) => {
+        $crate::log!($crate::log::Level::Trace, $($record)+)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::capture_output;
+
+    #[test]
+    fn test_log_records() {
+        let signer = "alice";
+        let ((), lines) = capture_output(|| {
+            crate::info!("Deposit accepted");
+            crate::warn!("Balance below {}", 10; signer = signer, balance = 5u32,);
+            crate::log!(Level::Error, "Rejected {signer}");
+        });
+        let expected: Vec<&str> = [
+            (
+                Level::Info,
+                "INFO kore_contract_sdk::log::tests: Deposit accepted",
+            ),
+            (
+                Level::Warn,
+                "WARN kore_contract_sdk::log::tests: Balance below 10 signer=\"alice\" balance=5",
+            ),
+            (
+                Level::Error,
+                "ERROR kore_contract_sdk::log::tests: Rejected alice",
+            ),
+        ]
+        .into_iter()
+        .filter(|(level, _)| enabled(*level))
+        .map(|(_, line)| line)
+        .collect();
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_levels() {
+        assert!(Level::Error < Level::Trace);
+        assert_eq!(Level::Debug.to_string(), "DEBUG");
+        assert_eq!(enabled(Level::Error), MAX_LEVEL.is_some());
+    }
+}
@@ -0,0 +1,372 @@
+// Copyright 2025 Kore Ledger
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Contract logic callbacks.
+//!
+//! Besides filling a `ContractResult`, the contract logic can return a `Result`, so success
+//! can not be forgotten: `Ok` is a successful execution and `Err` its error.
+//!
+//! ```ignore
+//! #[contract]
+//! fn contract_logic(
+//!     context: &Context<State, Event>,
+//!     state: &mut State,
+//! ) -> Result<(), ContractError> {
+//!     match context.event {
+//!         Event::Withdraw { amount } => {
+//!             ensure!(amount <= state.balance, "Insufficient balance");
+//!             state.balance -= amount;
+//!         }
+//!         Event::Close if !context.is_owner => {
+//!             return reject(ContractError::authorization(codes::USER, "Not the owner"));
+//!         }
+//!         Event::Close => state.closed = true,
+//!     }
+//!     Ok(())
+//! }
+//!
+//! #[contract(init)]
+//! fn init_logic(state: &State) -> Result<(), ContractError> {
+//!     ensure!(state.balance == 0, "The initial balance must be zero");
+//!     Ok(())
+//! }
+//! ```
+
+use crate::{Context, ContractError, ContractInitCheck, ContractResult, Outputs};
+
+/// Contract logic accepted by `execute_contract`. It is implemented for functions with
+/// these signatures:
+///
+/// * `fn(&Context<State, Event>, &mut ContractResult<State>)` - Fills the result, which must be
+///   set as successful. Output events are emitted with `ContractResult::emit`.
+/// * `fn(&Context<State, Event>) -> Result<State, ContractError>` - Returns the final state.
+/// * `fn(&Context<State, Event>, &mut State) -> Result<(), ContractError>` - Updates the final
+///   state in place. It starts as the initial state.
+/// * `fn(&Context<State, Event>, &mut Outputs) -> Result<State, ContractError>` and
+///   `fn(&Context<State, Event>, &mut State, &mut Outputs) -> Result<(), ContractError>` - Like
+///   the previous two, emitting output events with `Outputs::emit`.
+///
+/// `Marker` tells the signatures apart and is inferred.
+pub trait ContractLogic<State, Event, Marker> {
+    /// Runs the logic and records its outcome in `result`.
+    fn run(&self, context: &Context<State, Event>, result: &mut ContractResult<State>);
+}
+
+/// Init logic accepted by `check_init_data`. It is implemented for functions with these
+/// signatures:
+///
+/// * `fn(&State, &mut ContractInitCheck)` - Fills the result, which must be set as successful.
+/// * `fn(&State) -> Result<(), ContractError>`
+///
+/// `Marker` tells the signatures apart and is inferred.
+pub trait InitLogic<State, Marker> {
+    /// Runs the logic and records its outcome in `result`.
+    fn run(&self, state: &State, result: &mut ContractInitCheck);
+}
+
+/// Markers of the callback signatures.
+#[doc(hidden)]
+pub mod markers {
+    pub struct FillsResult;
+    pub struct ReturnsState;
+    pub struct UpdatesState;
+    pub struct ReturnsStateWithOutputs;
+    pub struct UpdatesStateWithOutputs;
+}
+
+impl<State, Event, F> ContractLogic<State, Event, markers::FillsResult> for F
+where
+    F: Fn(&Context<State, Event>, &mut ContractResult<State>),
+{
+    fn run(&self, context: &Context<State, Event>, result: &mut ContractResult<State>) {
+        self(context, result)
+    }
+}
+
+impl<State, Event, F> ContractLogic<State, Event, markers::ReturnsState> for F
+where
+    F: Fn(&Context<State, Event>) -> Result<State, ContractError>,
+{
+    fn run(&self, context: &Context<State, Event>, result: &mut ContractResult<State>) {
+        match self(context) {
+            Ok(final_state) => {
+                result.final_state = final_state;
+                result.success = true;
+            }
+            Err(error) => {
+                result.success = false;
+                result.error = Some(error);
+            }
+        }
+    }
+}
+
+impl<State, Event, F> ContractLogic<State, Event, markers::UpdatesState> for F
+where
+    F: Fn(&Context<State, Event>, &mut State) -> Result<(), ContractError>,
+{
+    fn run(&self, context: &Context<State, Event>, result: &mut ContractResult<State>) {
+        match self(context, &mut result.final_state) {
+            Ok(()) => result.success = true,
+            Err(error) => {
+                result.success = false;
+                result.error = Some(error);
+            }
+        }
+    }
+}
+
+impl<State, Event, F> ContractLogic<State, Event, markers::ReturnsStateWithOutputs> for F
+where
+    F: Fn(&Context<State, Event>, &mut Outputs) -> Result<State, ContractError>,
+{
+    fn run(&self, context: &Context<State, Event>, result: &mut ContractResult<State>) {
+        let mut outputs = Outputs::default();
+        match self(context, &mut outputs) {
+            Ok(final_state) => {
+                result.final_state = final_state;
+                result.success = true;
+                result.outputs.extend(outputs.0);
+            }
+            Err(error) => {
+                result.success = false;
+                result.error = Some(error);
+            }
+        }
+    }
+}
+
+impl<State, Event, F> ContractLogic<State, Event, markers::UpdatesStateWithOutputs> for F
+where
+    F: Fn(&Context<State, Event>, &mut State, &mut Outputs) -> Result<(), ContractError>,
+{
+    fn run(&self, context: &Context<State, Event>, result: &mut ContractResult<State>) {
+        let mut outputs = Outputs::default();
+        match self(context, &mut result.final_state, &mut outputs) {
+            Ok(()) => {
+                result.success = true;
+                result.outputs.extend(outputs.0);
+            }
+            Err(error) => {
+                result.success = false;
+                result.error = Some(error);
+            }
+        }
+    }
+}
+
+impl<State, F> InitLogic<State, markers::FillsResult> for F
+where
+    F: Fn(&State, &mut ContractInitCheck),
+{
+    fn run(&self, state: &State, result: &mut ContractInitCheck) {
+        self(state, result)
+    }
+}
+
+impl<State, F> InitLogic<State, markers::UpdatesState> for F
+where
+    F: Fn(&State) -> Result<(), ContractError>,
+{
+    fn run(&self, state: &State, result: &mut ContractInitCheck) {
+        match self(state) {
+            Ok(()) => result.success = true,
+            Err(error) => {
+                result.success = false;
+                result.error = Some(error);
+            }
+        }
+    }
+}
+
+/// Fails the contract logic with `error`.
+///
+/// ```ignore
+/// return reject(ContractError::authorization(codes::USER, "Not the owner"));
+/// ```
+pub fn reject<T>(error: impl Into<ContractError>) -> Result<T, ContractError> {
+    Err(error.into())
+}
+
+/// Fails the contract logic with an error unless a condition holds.
+///
+/// The error is anything that converts into a `ContractError`, or a format string and its
+/// arguments for an error without a specific code.
+///
+/// ```ignore
+/// ensure!(amount <= state.balance, ContractError::validation(codes::USER, "No funds"));
+/// ensure!(context.is_owner, "{} is not the owner", context.signer);
+/// ```
+#[macro_export]
+macro_rules! ensure {
+    ($condition:expr, $error:expr $(,)?) => {
+        if !$condition {
+            return ::core::result::Result::Err(::core::convert::Into::<$crate::ContractError>::into(
+                $error,
+            ));
+        }
+    };
+    ($condition:expr, $format:literal, $($argument:tt)+) => {
+        if !$condition {
+            return ::core::result::Result::Err($crate::ContractError::from(::std::format!(
+                $format,
+                $($argument)+
+            )));
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::{
+        ErrorCategory, codes,
+        testing::{Invocation, check_init},
+    };
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    struct State {
+        balance: u32,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    enum Event {
+        Deposit(u32),
+        Withdraw(u32),
+    }
+
+    fn update(context: &Context<State, Event>, state: &mut State) -> Result<(), ContractError> {
+        match context.event {
+            Event::Deposit(amount) => state.balance += amount,
+            Event::Withdraw(amount) => {
+                ensure!(context.is_owner, "{} is not the owner", context.signer);
+                ensure!(
+                    amount <= state.balance,
+                    ContractError::validation(codes::USER, "Insufficient balance")
+                );
+                state.balance -= amount;
+            }
+        }
+        Ok(())
+    }
+
+    fn replace(context: &Context<State, Event>) -> Result<State, ContractError> {
+        match context.event {
+            Event::Deposit(balance) => Ok(State { balance }),
+            Event::Withdraw(_) => reject(ContractError::authorization(codes::USER, "Closed")),
+        }
+    }
+
+    #[test]
+    fn test_result_logic() {
+        let result = Invocation::new(State { balance: 1 }, Event::Withdraw(1))
+            .owner(true)
+            .run(update);
+        assert!(result.success);
+        assert_eq!(result.final_state, Some(State { balance: 0 }));
+
+        let result = Invocation::new(State { balance: 1 }, Event::Withdraw(2))
+            .owner(true)
+            .run(update);
+        assert!(!result.success);
+        assert_eq!(result.error.unwrap().message, "Insufficient balance");
+
+        let result = Invocation::new(State { balance: 1 }, Event::Withdraw(1))
+            .signer("alice")
+            .run(update);
+        let error = result.error.unwrap();
+        assert_eq!(
+            (error.code, error.message.as_str()),
+            (codes::UNSPECIFIED, "alice is not the owner")
+        );
+
+        let result = Invocation::new(State { balance: 1 }, Event::Deposit(5)).run(replace);
+        assert!(result.success);
+        assert_eq!(result.final_state, Some(State { balance: 5 }));
+
+        let result = Invocation::new(State { balance: 1 }, Event::Withdraw(5)).run(replace);
+        assert!(!result.success);
+        assert_eq!(result.error.unwrap().category, ErrorCategory::Authorization);
+    }
+
+    #[test]
+    fn test_result_logic_outputs() {
+        fn deposit(
+            context: &Context<State, Event>,
+            state: &mut State,
+            outputs: &mut Outputs,
+        ) -> Result<(), ContractError> {
+            if let Event::Deposit(amount) = context.event {
+                outputs.emit(&("deposited", amount))?;
+            }
+            update(context, state)
+        }
+
+        fn open(
+            context: &Context<State, Event>,
+            outputs: &mut Outputs,
+        ) -> Result<State, ContractError> {
+            outputs.emit(&"opened")?;
+            replace(context)
+        }
+
+        fn run<M>(
+            event: Event,
+            logic: impl ContractLogic<State, Event, M>,
+        ) -> ContractResult<State> {
+            let context = Context {
+                initial_state: State { balance: 1 },
+                event,
+                is_owner: false,
+                signer: String::new(),
+                subject_id: String::new(),
+                governance_id: String::new(),
+                sn: 1,
+                timestamp: 0,
+            };
+            let mut result = ContractResult::new(State { balance: 1 });
+            logic.run(&context, &mut result);
+            result
+        }
+
+        let outputs = |result: &ContractResult<State>| -> Vec<serde_json::Value> {
+            result
+                .outputs
+                .iter()
+                .map(|output| output.0.clone())
+                .collect()
+        };
+
+        let result = run(Event::Deposit(2), deposit);
+        assert!(result.success);
+        assert_eq!(result.final_state, State { balance: 3 });
+        assert_eq!(outputs(&result), [serde_json::json!(["deposited", 2])]);
+
+        let result = run(Event::Deposit(4), open);
+        assert_eq!(result.final_state, State { balance: 4 });
+        assert_eq!(outputs(&result), [serde_json::json!("opened")]);
+
+        // Failed executions do not emit their output events.
+        let result = run(Event::Withdraw(1), open);
+        assert!(!result.success);
+        assert!(result.outputs.is_empty());
+    }
+
+    #[test]
+    fn test_result_init_logic() {
+        fn init(state: &State) -> Result<(), ContractError> {
+            ensure!(state.balance == 0, "The initial balance must be zero");
+            Ok(())
+        }
+
+        assert!(check_init(&State { balance: 0 }, init).success);
+        let result = check_init(&State { balance: 1 }, init);
+        assert!(!result.success);
+        assert_eq!(
+            result.error.unwrap().message,
+            "The initial balance must be zero"
+        );
+    }
+}
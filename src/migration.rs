@@ -0,0 +1,189 @@
+// Copyright 2025 Kore Ledger
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! State versioning and migrations.
+//!
+//! A versioned state is stored as `{ "$version": N, "$state": <state> }`. States without this
+//! envelope were stored before versioning was enabled and are considered version `0`. To
+//! store the initial state of new subjects with the current version, the contract must use the
+//! constructor, `init_contract_with`, with the same migrations.
+//!
+//! ```ignore
+//! fn migrate_v0_to_v1(mut state: Value) -> Result<Value, ContractError> {
+//!     state["owner"] = Value::Null;
+//!     Ok(state)
+//! }
+//!
+//! fn contract_options() -> ExecutionOptions<State, Event> {
+//!     ExecutionOptions::default().migrations(Migrations::new(1).migration(0, migrate_v0_to_v1))
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+
+use serde_json::{Map, Value};
+
+use crate::{ContractError, codes};
+
+/// Key of the state version in the envelope.
+pub const VERSION_KEY: &str = "$version";
+/// Key of the state in the envelope.
+pub const STATE_KEY: &str = "$state";
+
+/// Migration of a state from version `N` to version `N + 1`.
+pub type Migration = fn(Value) -> Result<Value, ContractError>;
+
+/// Registry of the migrations up to the current state version.
+#[derive(Debug, Clone)]
+pub struct Migrations {
+    version: u32,
+    steps: BTreeMap<u32, Migration>,
+}
+
+impl Migrations {
+    /// Creates a registry whose current state version is `version`.
+    pub fn new(version: u32) -> Self {
+        Self {
+            version,
+            steps: BTreeMap::new(),
+        }
+    }
+
+    /// Registers the migration from version `from` to version `from + 1`.
+    pub fn migration(mut self, from: u32, migration: Migration) -> Self {
+        self.steps.insert(from, migration);
+        self
+    }
+
+    /// Current state version.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Migrates a stored state to the current version and returns it without the envelope.
+    ///
+    /// Unversioned states are considered version `0`.
+    pub fn migrate(&self, stored: Value) -> Result<Value, ContractError> {
+        let (version, state) = split(stored).unwrap_or_else(|stored| (0, stored));
+        self.migrate_from(version, state)
+    }
+
+    /// Migrates an init state to the current version and returns it without the envelope.
+    ///
+    /// Unversioned init states come from the governance and are considered to be already in
+    /// the current version.
+    pub fn migrate_init(&self, stored: Value) -> Result<Value, ContractError> {
+        let (version, state) = split(stored).unwrap_or_else(|stored| (self.version, stored));
+        self.migrate_from(version, state)
+    }
+
+    /// Wraps a state of the current version in the envelope.
+    pub fn wrap(&self, state: Value) -> Value {
+        wrap(self.version, state)
+    }
+
+    fn migrate_from(&self, version: u32, mut state: Value) -> Result<Value, ContractError> {
+        if version > self.version {
+            return Err(ContractError::internal(
+                codes::UNSUPPORTED_STATE_VERSION,
+                format!(
+                    "State version {} is newer than the contract state version {}",
+                    version, self.version
+                ),
+            ));
+        }
+        for from in version..self.version {
+            let Some(migration) = self.steps.get(&from) else {
+                return Err(ContractError::internal(
+                    codes::STATE_MIGRATION,
+                    format!("Missing state migration from version {}", from),
+                ));
+            };
+            state = migration(state).map_err(|error| {
+                ContractError::internal(
+                    codes::STATE_MIGRATION,
+                    format!(
+                        "State migration from version {} failed: {}",
+                        from, error.message
+                    ),
+                )
+                .with_details(serde_json::to_value(&error).unwrap_or_default())
+            })?;
+        }
+        Ok(state)
+    }
+}
+
+/// Wraps `state` in the envelope of `version`.
+pub fn wrap(version: u32, state: Value) -> Value {
+    let mut envelope = Map::new();
+    envelope.insert(VERSION_KEY.to_owned(), Value::from(version));
+    envelope.insert(STATE_KEY.to_owned(), state);
+    Value::Object(envelope)
+}
+
+/// Splits an envelope into its version and state, or returns the value back if it is not
+/// an envelope.
+pub fn split(value: Value) -> Result<(u32, Value), Value> {
+    let version = match &value {
+        Value::Object(map) if map.len() == 2 && map.contains_key(STATE_KEY) => map
+            .get(VERSION_KEY)
+            .and_then(Value::as_u64)
+            .and_then(|version| u32::try_from(version).ok()),
+        _ => None,
+    };
+    match (version, value) {
+        (Some(version), Value::Object(mut map)) => {
+            Ok((version, map.remove(STATE_KEY).unwrap_or_default()))
+        }
+        (_, value) => Err(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn add_owner(mut state: Value) -> Result<Value, ContractError> {
+        state["owner"] = json!("unknown");
+        Ok(state)
+    }
+
+    fn rename_value(mut state: Value) -> Result<Value, ContractError> {
+        let Some(value) = state
+            .as_object_mut()
+            .and_then(|state| state.remove("value"))
+        else {
+            return Err("Missing value".into());
+        };
+        state["amount"] = value;
+        Ok(state)
+    }
+
+    #[test]
+    fn test_migrations() {
+        let migrations = Migrations::new(2)
+            .migration(0, add_owner)
+            .migration(1, rename_value);
+        let current = json!({ "amount": 1, "owner": "unknown" });
+
+        assert_eq!(migrations.migrate(json!({ "value": 1 })).unwrap(), current);
+        let stored = wrap(1, json!({ "value": 1, "owner": "unknown" }));
+        assert_eq!(migrations.migrate(stored).unwrap(), current);
+        assert_eq!(
+            migrations
+                .migrate(migrations.wrap(current.clone()))
+                .unwrap(),
+            current
+        );
+        assert_eq!(migrations.migrate_init(current.clone()).unwrap(), current);
+
+        let error = migrations.migrate(wrap(3, json!({}))).unwrap_err();
+        assert_eq!(error.code, codes::UNSUPPORTED_STATE_VERSION);
+        let error = migrations.migrate(wrap(1, json!({}))).unwrap_err();
+        assert_eq!(error.code, codes::STATE_MIGRATION);
+        let error = Migrations::new(1).migrate(json!({})).unwrap_err();
+        assert_eq!(error.message, "Missing state migration from version 0");
+    }
+}
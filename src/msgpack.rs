@@ -0,0 +1,212 @@
+// Copyright 2025 Kore Ledger
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Conversion between the `ValueWrapper` wire format and MessagePack.
+//!
+//! `from_wire` gives the MessagePack of a state or event, and `to_wire` the wire format of a
+//! MessagePack value, without going through `serde_json::Value`. A wire value converted to
+//! MessagePack and back is unchanged, except for exact number literals:
+//!
+//! - Booleans, `null`, strings, arrays, byte arrays (bin), integers and floats (float 64) map
+//!   to their MessagePack counterparts, and objects to maps with string keys in ascending
+//!   order.
+//! - Exact number literals, which MessagePack has no type for, are written as strings and
+//!   come back as strings. `Decimal`, `U256` and `I256` read their value from the string, so
+//!   contracts still get the exact number.
+//!
+//! MessagePack values without a wire format counterpart are converted with loss:
+//!
+//! - Float 32 numbers become float 64, with the same value. Non-finite floats become `null`.
+//! - Integer map keys become strings, as in JSON. Maps with other non-string keys or with a
+//!   repeated key are rejected.
+//! - Extension types, including timestamps, and strings that are not valid UTF-8 are
+//!   rejected.
+
+use rmpv::Value;
+
+use crate::{de::node::Node, error::ConvertError};
+
+const FORMAT: &str = "MessagePack";
+
+/// Converts a value in the wire format into MessagePack.
+pub fn from_wire(bytes: &[u8]) -> Result<Vec<u8>, ConvertError> {
+    let value = to_msgpack(Node::from_bytes(bytes)?);
+    let mut output = Vec::new();
+    rmpv::encode::write_value(&mut output, &value).map_err(format_error)?;
+    Ok(output)
+}
+
+/// Converts a MessagePack value into the wire format.
+pub fn to_wire(msgpack: &[u8]) -> Result<Vec<u8>, ConvertError> {
+    let mut reader = msgpack;
+    let value = rmpv::decode::read_value(&mut reader).map_err(format_error)?;
+    if !reader.is_empty() {
+        return Err(format_error("Trailing bytes after the value"));
+    }
+    Ok(from_msgpack(value)?.to_bytes())
+}
+
+fn format_error<E: ToString>(error: E) -> ConvertError {
+    ConvertError::Format {
+        format: FORMAT,
+        message: error.to_string(),
+    }
+}
+
+fn to_msgpack(node: Node) -> Value {
+    match node {
+        Node::Bool(value) => Value::Boolean(value),
+        Node::F64(value) => Value::F64(value),
+        Node::I64(value) => Value::from(value),
+        Node::U64(value) => Value::from(value),
+        Node::Literal(value) | Node::String(value) => Value::from(value),
+        Node::Array(elements) => Value::Array(elements.into_iter().map(to_msgpack).collect()),
+        Node::Object(entries) => Value::Map(
+            entries
+                .into_iter()
+                .map(|(key, value)| (Value::from(key), to_msgpack(value)))
+                .collect(),
+        ),
+        Node::Null => Value::Nil,
+        Node::Bytes(bytes) => Value::Binary(bytes),
+    }
+}
+
+fn from_msgpack(value: Value) -> Result<Node, ConvertError> {
+    Ok(match value {
+        Value::Nil => Node::Null,
+        Value::Boolean(value) => Node::Bool(value),
+        Value::Integer(integer) => match integer.as_i64() {
+            Some(integer) => Node::I64(integer),
+            None => Node::U64(integer.as_u64().unwrap_or_default()),
+        },
+        Value::F32(float) => Node::float(float.into()),
+        Value::F64(float) => Node::float(float),
+        Value::String(string) => Node::String(string_from_msgpack(string)?),
+        Value::Binary(bytes) => Node::Bytes(bytes),
+        Value::Array(elements) => Node::Array(
+            elements
+                .into_iter()
+                .map(from_msgpack)
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Map(entries) => Node::object(
+            entries
+                .into_iter()
+                .map(|(key, value)| Ok((key_from_msgpack(key)?, from_msgpack(value)?)))
+                .collect::<Result<_, ConvertError>>()?,
+        )?,
+        Value::Ext(kind, _) => {
+            return Err(ConvertError::Unsupported(format!(
+                "MessagePack extension type {}",
+                kind
+            )));
+        }
+    })
+}
+
+fn string_from_msgpack(string: rmpv::Utf8String) -> Result<String, ConvertError> {
+    string
+        .into_str()
+        .ok_or_else(|| format_error("Invalid UTF-8 string"))
+}
+
+fn key_from_msgpack(key: Value) -> Result<String, ConvertError> {
+    match key {
+        Value::String(key) => string_from_msgpack(key),
+        Value::Integer(key) => Ok(key.to_string()),
+        _ => Err(ConvertError::Unsupported(
+            "MessagePack map key that is not a string or an integer".to_owned(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{ValueWrapper, bytes::Bytes, ser::Serializer};
+
+    fn msgpack(value: &Value) -> Vec<u8> {
+        let mut output = Vec::new();
+        rmpv::encode::write_value(&mut output, value).unwrap();
+        output
+    }
+
+    #[test]
+    fn test_msgpack_round_trip() {
+        #[derive(serde::Serialize)]
+        struct State {
+            name: String,
+            count: u64,
+            balance: i64,
+            ratio: f64,
+            tags: Vec<Option<bool>>,
+            content: Bytes,
+        }
+
+        let state = State {
+            name: "Alice".to_owned(),
+            count: u64::MAX,
+            balance: -5,
+            ratio: 0.25,
+            tags: vec![Some(true), None],
+            content: Bytes(vec![1, 2]),
+        };
+        let mut serializer = Serializer::new().with_dedicated_tags(true);
+        serde::Serialize::serialize(&state, &mut serializer).unwrap();
+        let wire = serializer.into_bytes();
+        let encoded = from_wire(&wire).unwrap();
+        let Value::Map(entries) = rmpv::decode::read_value(&mut encoded.as_slice()).unwrap() else {
+            panic!("Expected a map");
+        };
+        assert_eq!(
+            entries[1],
+            (Value::from("content"), Value::Binary(vec![1, 2]))
+        );
+        assert_eq!(to_wire(&encoded).unwrap(), wire);
+
+        let literal = Node::Literal("1.50".to_owned()).to_bytes();
+        let wire = to_wire(&from_wire(&literal).unwrap()).unwrap();
+        assert_eq!(wire, Node::String("1.50".to_owned()).to_bytes());
+    }
+
+    #[test]
+    fn test_msgpack_lossy() {
+        let value = Value::Map(vec![
+            (Value::from(2), Value::F32(0.5)),
+            (Value::from("nan"), Value::F64(f64::NAN)),
+        ]);
+        let wire = to_wire(&msgpack(&value)).unwrap();
+        let expected = json!({ "2": 0.5, "nan": null });
+        assert_eq!(wire, borsh::to_vec(&ValueWrapper(expected)).unwrap());
+
+        let duplicate = Value::Map(vec![
+            (Value::from(1), Value::Nil),
+            (Value::from("1"), Value::Nil),
+        ]);
+        assert_eq!(
+            to_wire(&msgpack(&duplicate)),
+            Err(ConvertError::DuplicateKey("1".to_owned()))
+        );
+        let key = Value::Map(vec![(Value::Nil, Value::Nil)]);
+        assert!(matches!(
+            to_wire(&msgpack(&key)),
+            Err(ConvertError::Unsupported(_))
+        ));
+        let extension = Value::Ext(-1, vec![0; 4]);
+        assert!(matches!(
+            to_wire(&msgpack(&extension)),
+            Err(ConvertError::Unsupported(_))
+        ));
+
+        let mut trailing = msgpack(&Value::Nil);
+        trailing.push(0);
+        assert!(matches!(
+            to_wire(&trailing),
+            Err(ConvertError::Format { .. })
+        ));
+        assert!(matches!(from_wire(&[9]), Err(ConvertError::Decode(_))));
+    }
+}
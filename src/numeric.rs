@@ -0,0 +1,94 @@
+// Copyright 2025 Kore Ledger
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Serde support shared by `Decimal`, `U256` and `I256`.
+//!
+//! The numbers are always serialized as strings. In the wire format they are written with
+//! their own tag when the serializer writes dedicated tags, see
+//! `ser::Serializer::with_dedicated_tags`, and decoded back as the same string. Strings,
+//! integers and exact JSON numbers are accepted when deserializing. serde_json only gives a
+//! float to the visitor when it is an exact representation of the JSON number.
+
+use std::{fmt, marker::PhantomData, str::FromStr};
+
+use serde::{Deserializer, Serialize, Serializer, de};
+
+use crate::error::NumberError;
+
+/// Map key used by serde_json to pass arbitrary precision numbers through serde.
+const NUMBER_TOKEN: &str = "$serde_json::private::Number";
+
+/// Newtype name used by the wire format serializer to write the numbers with their own tag.
+pub(crate) const TOKEN: &str = "$kore_contract_sdk::private::Numeric";
+
+pub(crate) fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: fmt::Display,
+{
+    serializer.serialize_newtype_struct(TOKEN, &Literal(value))
+}
+
+/// Number serialized inside the `TOKEN` newtype, as its string.
+struct Literal<'a, T>(&'a T);
+
+impl<T: fmt::Display> Serialize for Literal<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self.0)
+    }
+}
+
+pub(crate) fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr<Err = NumberError>,
+{
+    deserializer.deserialize_any(Visitor(PhantomData))
+}
+
+struct Visitor<T>(PhantomData<T>);
+
+impl<T: FromStr<Err = NumberError>> Visitor<T> {
+    fn parse<E: de::Error>(literal: &str) -> Result<T, E> {
+        literal.parse().map_err(E::custom)
+    }
+}
+
+impl<'de, T: FromStr<Err = NumberError>> de::Visitor<'de> for Visitor<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a number string or an integer")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<T, E> {
+        Self::parse(value)
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<T, E> {
+        Self::parse(&value.to_string())
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<T, E> {
+        Self::parse(&value.to_string())
+    }
+
+    fn visit_i128<E: de::Error>(self, value: i128) -> Result<T, E> {
+        Self::parse(&value.to_string())
+    }
+
+    fn visit_u128<E: de::Error>(self, value: u128) -> Result<T, E> {
+        Self::parse(&value.to_string())
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<T, E> {
+        Self::parse(&value.to_string())
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<T, A::Error> {
+        match map.next_entry::<String, String>()? {
+            Some((key, literal)) if key == NUMBER_TOKEN => Self::parse(&literal),
+            _ => Err(de::Error::invalid_type(de::Unexpected::Map, &self)),
+        }
+    }
+}
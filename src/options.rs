@@ -0,0 +1,369 @@
+// Copyright 2025 Kore Ledger
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    ContractError, ErrorCategory, ValidationError, codes,
+    de::DecodeLimits,
+    migration::Migrations,
+    validation,
+    value_wrapper::{float_value, integral, normalize},
+};
+
+/// How the final state is returned to the host.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResultMode {
+    /// The whole final state is returned.
+    #[default]
+    FullState,
+    /// A JSON Patch over the received state is returned, or an unchanged marker if the event
+    /// did not modify it.
+    Patch,
+}
+
+/// What happens to a batch of events when one of them fails.
+///
+/// In both modes the events after the failed one are not applied.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+pub enum BatchMode {
+    /// No event is applied: the final state is the received state.
+    #[default]
+    AllOrNothing,
+    /// The events before the failed one are applied: the final state is the state after
+    /// them.
+    StopAtFirstFailure,
+}
+
+/// What to do when the state received from the host can not be converted into `State`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StateFallback {
+    /// The init state of the subject is used instead. The fallback is reported to the host
+    /// with a `Level::Warn` log record, see the `log` module.
+    InitState,
+    /// The execution fails with a `codes::STATE_CONVERSION` error.
+    #[default]
+    Reject,
+}
+
+/// How floating-point numbers in states and events are handled.
+///
+/// Float arithmetic is not guaranteed to give the same result on every evaluator, so
+/// contracts that must reach consensus should use integers or `Decimal` instead.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FloatPolicy {
+    /// Floats are accepted as they are.
+    #[default]
+    Allow,
+    /// Any float fails with a `codes::FLOAT_NOT_ALLOWED` error.
+    Reject,
+    /// Floats without a fractional part, such as `2.0`, are converted into integers. Any
+    /// other float fails with a `codes::FLOAT_NOT_ALLOWED` error.
+    Normalize,
+}
+
+impl FloatPolicy {
+    /// Checks the floats of `value`, returning the normalized value if it changed.
+    pub(crate) fn apply(self, value: &Value) -> Result<Option<Value>, ContractError> {
+        if self == FloatPolicy::Allow || !self.check(value, &mut String::new())? {
+            return Ok(None);
+        }
+        let mut value = value.clone();
+        normalize(&mut value);
+        Ok(Some(value))
+    }
+
+    /// Fails on the first float not allowed by the policy and returns whether any float has
+    /// to be normalized.
+    fn check(self, value: &Value, path: &mut String) -> Result<bool, ContractError> {
+        let mut normalize = false;
+        match value {
+            Value::Number(number) if number.is_f64() => {
+                let integer = float_value(number).and_then(integral);
+                if self == FloatPolicy::Reject || integer.is_none() {
+                    return Err(ContractError::validation(
+                        codes::FLOAT_NOT_ALLOWED,
+                        format!(
+                            "Floating-point number {} at '{}' is not allowed",
+                            number, path
+                        ),
+                    )
+                    .with_details(serde_json::json!({ "path": path })));
+                }
+                normalize = true;
+            }
+            Value::Array(array) => {
+                for (index, item) in array.iter().enumerate() {
+                    let len = path.len();
+                    path.push('/');
+                    path.push_str(&index.to_string());
+                    normalize |= self.check(item, path)?;
+                    path.truncate(len);
+                }
+            }
+            Value::Object(object) => {
+                for (key, item) in object {
+                    let len = path.len();
+                    path.push('/');
+                    path.push_str(&key.replace('~', "~0").replace('/', "~1"));
+                    normalize |= self.check(item, path)?;
+                    path.truncate(len);
+                }
+            }
+            _ => {}
+        }
+        Ok(normalize)
+    }
+}
+
+/// Invariant checked over a deserialized value.
+pub type Invariant<T> = fn(&T) -> Result<(), ContractError>;
+
+/// Contract execution options.
+pub struct ExecutionOptions<State, Event> {
+    pub(crate) result_mode: ResultMode,
+    pub(crate) state_fallback: StateFallback,
+    pub(crate) state_schema: Option<Value>,
+    pub(crate) event_schema: Option<Value>,
+    pub(crate) state_invariant: Option<Invariant<State>>,
+    pub(crate) event_invariant: Option<Invariant<Event>>,
+    pub(crate) migrations: Option<Migrations>,
+    pub(crate) canonical: bool,
+    pub(crate) dedicated_tags: bool,
+    pub(crate) float_policy: FloatPolicy,
+    pub(crate) decode_limits: DecodeLimits,
+    pub(crate) batch_mode: BatchMode,
+}
+
+impl<State, Event> Default for ExecutionOptions<State, Event> {
+    fn default() -> Self {
+        Self {
+            result_mode: ResultMode::default(),
+            state_fallback: StateFallback::default(),
+            state_schema: None,
+            event_schema: None,
+            state_invariant: None,
+            event_invariant: None,
+            migrations: None,
+            canonical: false,
+            dedicated_tags: false,
+            float_policy: FloatPolicy::default(),
+            decode_limits: DecodeLimits::default(),
+            batch_mode: BatchMode::default(),
+        }
+    }
+}
+
+impl<State, Event> Clone for ExecutionOptions<State, Event> {
+    fn clone(&self) -> Self {
+        Self {
+            result_mode: self.result_mode,
+            state_fallback: self.state_fallback,
+            state_schema: self.state_schema.clone(),
+            event_schema: self.event_schema.clone(),
+            state_invariant: self.state_invariant,
+            event_invariant: self.event_invariant,
+            migrations: self.migrations.clone(),
+            canonical: self.canonical,
+            dedicated_tags: self.dedicated_tags,
+            float_policy: self.float_policy,
+            decode_limits: self.decode_limits,
+            batch_mode: self.batch_mode,
+        }
+    }
+}
+
+impl<State, Event> ExecutionOptions<State, Event> {
+    /// Sets how the final state is returned to the host.
+    pub fn result_mode(mut self, result_mode: ResultMode) -> Self {
+        self.result_mode = result_mode;
+        self
+    }
+
+    /// Sets what to do when the received state does not match `State`.
+    pub fn state_fallback(mut self, state_fallback: StateFallback) -> Self {
+        self.state_fallback = state_fallback;
+        self
+    }
+
+    /// Validates the received and final states against a JSON Schema.
+    pub fn state_schema(mut self, schema: Value) -> Self {
+        self.state_schema = Some(schema);
+        self
+    }
+
+    /// Validates the received event against a JSON Schema.
+    pub fn event_schema(mut self, schema: Value) -> Self {
+        self.event_schema = Some(schema);
+        self
+    }
+
+    /// Checks the received and final states with `invariant`.
+    pub fn state_invariant(mut self, invariant: Invariant<State>) -> Self {
+        self.state_invariant = Some(invariant);
+        self
+    }
+
+    /// Checks the received event with `invariant`.
+    pub fn event_invariant(mut self, invariant: Invariant<Event>) -> Self {
+        self.event_invariant = Some(invariant);
+        self
+    }
+
+    /// Stores the state versioned and migrates older states before running the contract.
+    pub fn migrations(mut self, migrations: Migrations) -> Self {
+        self.migrations = Some(migrations);
+        self
+    }
+
+    /// Normalizes the numbers of the final state, so the returned state or patch is
+    /// canonically encoded. See `ValueWrapper::normalize`.
+    pub fn canonical(mut self, canonical: bool) -> Self {
+        self.canonical = canonical;
+        self
+    }
+
+    /// Encodes the byte arrays of `crate::bytes` and the SDK number types in the final state
+    /// with their own tags, see `ser::Serializer::with_dedicated_tags`. Hosts with an SDK older
+    /// than these tags can not decode them. Only a final state encoded straight from `State`
+    /// has the tags, not one that an option needs as a `Value` first, e.g. a schema or the
+    /// canonical form.
+    pub fn dedicated_tags(mut self, dedicated_tags: bool) -> Self {
+        self.dedicated_tags = dedicated_tags;
+        self
+    }
+
+    /// Sets how floats in the received state and event and in the final state are handled.
+    pub fn float_policy(mut self, float_policy: FloatPolicy) -> Self {
+        self.float_policy = float_policy;
+        self
+    }
+
+    /// Sets the limits for decoding the received state and event.
+    pub fn decode_limits(mut self, decode_limits: DecodeLimits) -> Self {
+        self.decode_limits = decode_limits;
+        self
+    }
+
+    /// Sets what happens to a batch of events when one of them fails.
+    pub fn batch_mode(mut self, batch_mode: BatchMode) -> Self {
+        self.batch_mode = batch_mode;
+        self
+    }
+
+    /// Validates states and events against the schemas generated for the contract types.
+    #[cfg(feature = "schema")]
+    pub fn contract_schema(self, schema: crate::schema::ContractSchema) -> Self {
+        self.state_schema(schema.state).event_schema(schema.event)
+    }
+}
+
+impl<State: Serialize, Event: Serialize> ExecutionOptions<State, Event> {
+    /// Whether the state can be deserialized directly from the wire bytes, i.e. no option
+    /// needs the received state as a `Value`.
+    pub(crate) fn typed_state(&self) -> bool {
+        self.result_mode == ResultMode::FullState
+            && self.state_schema.is_none()
+            && self.migrations.is_none()
+            && self.float_policy == FloatPolicy::Allow
+    }
+
+    /// Whether the event can be deserialized directly from the wire bytes.
+    pub(crate) fn typed_event(&self) -> bool {
+        self.event_schema.is_none() && self.float_policy == FloatPolicy::Allow
+    }
+
+    /// Checks a state against the state schema and invariant, failing with `code`.
+    ///
+    /// `value` is the state as a `Value`, if it is at hand.
+    pub(crate) fn check_state(
+        &self,
+        value: Option<&Value>,
+        state: &State,
+        code: u32,
+    ) -> Result<(), ContractError> {
+        let category = ErrorCategory::Validation;
+        check(&self.state_schema, self.state_invariant, value, state).map_err(|error| {
+            let message = match code {
+                codes::INVALID_FINAL_STATE => "Invalid final state",
+                _ => "Invalid state",
+            };
+            error.into_contract_error(category, code, message)
+        })
+    }
+
+    /// Checks an event against the event schema and invariant.
+    pub(crate) fn check_event(
+        &self,
+        value: Option<&Value>,
+        event: &Event,
+    ) -> Result<(), ContractError> {
+        check(&self.event_schema, self.event_invariant, value, event).map_err(|error| {
+            error.into_contract_error(
+                ErrorCategory::InvalidEvent,
+                codes::INVALID_EVENT,
+                "Invalid event",
+            )
+        })
+    }
+}
+
+/// Failure of a schema or invariant check.
+enum CheckError {
+    Schema(ValidationError),
+    Invariant(ContractError),
+}
+
+impl CheckError {
+    fn into_contract_error(
+        self,
+        category: ErrorCategory,
+        code: u32,
+        message: &str,
+    ) -> ContractError {
+        let (reason, details) = match self {
+            CheckError::Schema(error) => (
+                error.to_string(),
+                serde_json::json!({ "path": error.path, "message": error.message }),
+            ),
+            CheckError::Invariant(error) => (error.message.clone(), to_details(&error)),
+        };
+        ContractError::new(category, code, format!("{}: {}", message, reason)).with_details(details)
+    }
+}
+
+fn to_details<T: Serialize>(data: &T) -> Value {
+    serde_json::to_value(data).unwrap_or_default()
+}
+
+fn check<T: Serialize>(
+    schema: &Option<Value>,
+    invariant: Option<Invariant<T>>,
+    value: Option<&Value>,
+    data: &T,
+) -> Result<(), CheckError> {
+    if let Some(schema) = schema {
+        let result = match value {
+            Some(value) => validation::validate(schema, value),
+            None => validation::validate(schema, &serde_json::to_value(data).unwrap_or_default()),
+        };
+        result.map_err(CheckError::Schema)?;
+    }
+    if let Some(invariant) = invariant {
+        invariant(data).map_err(CheckError::Invariant)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,76 @@
+// Copyright 2025 Kore Ledger
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Panics of the contract logic.
+//!
+//! A panic hook keeps the message and location of every panic and, on wasm, reports them to
+//! the host through `cout`, so a trapped instance still leaves a trace on the node. The hook
+//! installed before it, e.g. the one of the test harness, still runs afterwards. The contract
+//! logic runs inside `catch`, which turns the panic into a `codes::CONTRACT_PANIC` error when
+//! it unwinds. Wasm contracts built with `panic = "abort"`, the default of
+//! `wasm32-unknown-unknown`, still trap after the hook has run.
+//!
+//! Native builds do not report through `cout`: the mock host of `testing` allocates in a
+//! `RefCell`, which may already be borrowed by the code that panicked.
+
+use std::{
+    any::Any,
+    cell::RefCell,
+    panic::{self, AssertUnwindSafe, PanicHookInfo},
+    sync::Once,
+};
+
+use crate::{ContractError, codes};
+
+static INSTALL: Once = Once::new();
+
+thread_local! {
+    /// Description of the last panic, taken by `catch`.
+    static LAST_PANIC: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Installs the panic hook, once per process, chained to the previous one.
+pub(crate) fn install() {
+    INSTALL.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            report(info);
+            previous(info);
+        }));
+    });
+}
+
+/// Runs `f`, converting a panic into a contract error.
+pub(crate) fn catch<R>(f: impl FnOnce() -> R) -> Result<R, ContractError> {
+    install();
+    LAST_PANIC.with_borrow_mut(|last| *last = None);
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+        let description = LAST_PANIC
+            .with_borrow_mut(Option::take)
+            .unwrap_or_else(|| format!("Contract panicked: {}", message(payload.as_ref())));
+        ContractError::internal(codes::CONTRACT_PANIC, description)
+    })
+}
+
+fn report(info: &PanicHookInfo) {
+    let message = message(info.payload());
+    let description = match info.location() {
+        Some(location) => format!("Contract panicked at {}: {}", location, message),
+        None => format!("Contract panicked: {}", message),
+    };
+    #[cfg(target_arch = "wasm32")]
+    unsafe {
+        crate::externf::cout(crate::store_bytes(description.as_bytes()))
+    };
+    LAST_PANIC.with_borrow_mut(|last| *last = Some(description));
+}
+
+fn message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "Box<dyn Any>"
+    }
+}
@@ -0,0 +1,358 @@
+// Copyright 2025 Kore Ledger
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! JSON Patch (RFC 6902) support for returning state changes instead of the whole state.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{ValueWrapper, error::PatchError};
+
+/// JSON Patch operation as defined by RFC 6902.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOperation {
+    /// Adds `value` at `path`.
+    Add { path: String, value: ValueWrapper },
+    /// Removes the value at `path`.
+    Remove { path: String },
+    /// Replaces the value at `path` with `value`.
+    Replace { path: String, value: ValueWrapper },
+    /// Moves the value at `from` to `path`.
+    Move { from: String, path: String },
+    /// Copies the value at `from` to `path`.
+    Copy { from: String, path: String },
+    /// Checks that the value at `path` is equal to `value`.
+    Test { path: String, value: ValueWrapper },
+}
+
+/// Changes of the state produced by a contract execution.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+pub enum StatePatch {
+    /// The event did not modify the state.
+    Unchanged,
+    /// JSON Patch that transforms the received state into the final state.
+    Patch(Vec<PatchOperation>),
+}
+
+/// Computes the patch that transforms `from` into `to`.
+///
+/// Only `add`, `remove` and `replace` operations are generated.
+pub fn diff(from: &Value, to: &Value) -> StatePatch {
+    let mut operations = Vec::new();
+    diff_values(&mut String::new(), from, to, &mut operations);
+    if operations.is_empty() {
+        StatePatch::Unchanged
+    } else {
+        StatePatch::Patch(operations)
+    }
+}
+
+/// Applies `patch` over `state`.
+///
+/// The patch is atomic: if any operation fails, `state` is left untouched.
+pub fn apply(state: &mut Value, patch: &StatePatch) -> Result<(), PatchError> {
+    match patch {
+        StatePatch::Unchanged => Ok(()),
+        StatePatch::Patch(operations) => apply_operations(state, operations),
+    }
+}
+
+/// Applies a list of JSON Patch operations over `state`.
+///
+/// The operations are atomic: if any of them fails, `state` is left untouched.
+pub fn apply_operations(
+    state: &mut Value,
+    operations: &[PatchOperation],
+) -> Result<(), PatchError> {
+    let mut patched = state.clone();
+    for operation in operations {
+        apply_operation(&mut patched, operation)?;
+    }
+    *state = patched;
+    Ok(())
+}
+
+fn diff_values(path: &mut String, from: &Value, to: &Value, operations: &mut Vec<PatchOperation>) {
+    if from == to {
+        return;
+    }
+    match (from, to) {
+        (Value::Object(from), Value::Object(to)) => {
+            for key in from.keys().filter(|key| !to.contains_key(*key)) {
+                operations.push(PatchOperation::Remove {
+                    path: child_path(path, key),
+                });
+            }
+            for (key, to_value) in to {
+                match from.get(key) {
+                    Some(from_value) => {
+                        let len = path.len();
+                        push_token(path, key);
+                        diff_values(path, from_value, to_value, operations);
+                        path.truncate(len);
+                    }
+                    None => operations.push(PatchOperation::Add {
+                        path: child_path(path, key),
+                        value: ValueWrapper(to_value.clone()),
+                    }),
+                }
+            }
+        }
+        (Value::Array(from), Value::Array(to)) => {
+            for (index, (from_value, to_value)) in from.iter().zip(to).enumerate() {
+                let len = path.len();
+                push_token(path, &index.to_string());
+                diff_values(path, from_value, to_value, operations);
+                path.truncate(len);
+            }
+            for index in (to.len()..from.len()).rev() {
+                operations.push(PatchOperation::Remove {
+                    path: child_path(path, &index.to_string()),
+                });
+            }
+            for (index, value) in to.iter().enumerate().skip(from.len()) {
+                operations.push(PatchOperation::Add {
+                    path: child_path(path, &index.to_string()),
+                    value: ValueWrapper(value.clone()),
+                });
+            }
+        }
+        _ => operations.push(PatchOperation::Replace {
+            path: path.clone(),
+            value: ValueWrapper(to.clone()),
+        }),
+    }
+}
+
+fn push_token(path: &mut String, token: &str) {
+    path.push('/');
+    path.push_str(&token.replace('~', "~0").replace('/', "~1"));
+}
+
+fn child_path(path: &str, token: &str) -> String {
+    let mut path = path.to_owned();
+    push_token(&mut path, token);
+    path
+}
+
+/// Splits a JSON Pointer (RFC 6901) into its unescaped reference tokens.
+fn parse_pointer(pointer: &str) -> Result<Vec<String>, PatchError> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(tokens) = pointer.strip_prefix('/') else {
+        return Err(PatchError::InvalidPointer(pointer.to_owned()));
+    };
+    Ok(tokens
+        .split('/')
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+fn array_index(token: &str, len: usize, pointer: &str) -> Result<usize, PatchError> {
+    let valid = !token.is_empty() && (token == "0" || !token.starts_with('0'));
+    match token.parse::<usize>() {
+        Ok(index) if valid && index <= len => Ok(index),
+        _ => Err(PatchError::InvalidIndex(pointer.to_owned())),
+    }
+}
+
+fn resolve<'a>(state: &'a Value, pointer: &str) -> Result<&'a Value, PatchError> {
+    let not_found = || PatchError::PathNotFound(pointer.to_owned());
+    let mut current = state;
+    for token in parse_pointer(pointer)? {
+        current = match current {
+            Value::Object(map) => map.get(&token).ok_or_else(not_found)?,
+            Value::Array(array) => array
+                .get(array_index(&token, array.len(), pointer)?)
+                .ok_or_else(not_found)?,
+            _ => return Err(not_found()),
+        };
+    }
+    Ok(current)
+}
+
+/// Resolves the parent container of `pointer` and returns it with the last token.
+fn resolve_parent<'a>(
+    state: &'a mut Value,
+    pointer: &str,
+) -> Result<(&'a mut Value, String), PatchError> {
+    let not_found = || PatchError::PathNotFound(pointer.to_owned());
+    let mut tokens = parse_pointer(pointer)?;
+    let last = tokens.pop().ok_or_else(not_found)?;
+    let mut current = state;
+    for token in tokens {
+        current = match current {
+            Value::Object(map) => map.get_mut(&token).ok_or_else(not_found)?,
+            Value::Array(array) => {
+                let index = array_index(&token, array.len(), pointer)?;
+                array.get_mut(index).ok_or_else(not_found)?
+            }
+            _ => return Err(not_found()),
+        };
+    }
+    Ok((current, last))
+}
+
+fn add(state: &mut Value, pointer: &str, value: Value) -> Result<(), PatchError> {
+    if pointer.is_empty() {
+        *state = value;
+        return Ok(());
+    }
+    let (parent, token) = resolve_parent(state, pointer)?;
+    match parent {
+        Value::Object(map) => {
+            map.insert(token, value);
+            Ok(())
+        }
+        Value::Array(array) => {
+            let index = if token == "-" {
+                array.len()
+            } else {
+                array_index(&token, array.len(), pointer)?
+            };
+            array.insert(index, value);
+            Ok(())
+        }
+        _ => Err(PatchError::PathNotFound(pointer.to_owned())),
+    }
+}
+
+fn remove(state: &mut Value, pointer: &str) -> Result<Value, PatchError> {
+    let not_found = || PatchError::PathNotFound(pointer.to_owned());
+    if pointer.is_empty() {
+        return Ok(std::mem::take(state));
+    }
+    let (parent, token) = resolve_parent(state, pointer)?;
+    match parent {
+        Value::Object(map) => map.remove(&token).ok_or_else(not_found),
+        Value::Array(array) => {
+            let index = array_index(&token, array.len(), pointer)?;
+            if index < array.len() {
+                Ok(array.remove(index))
+            } else {
+                Err(not_found())
+            }
+        }
+        _ => Err(not_found()),
+    }
+}
+
+fn apply_operation(state: &mut Value, operation: &PatchOperation) -> Result<(), PatchError> {
+    match operation {
+        PatchOperation::Add { path, value } => add(state, path, value.0.clone()),
+        PatchOperation::Remove { path } => remove(state, path).map(|_| ()),
+        PatchOperation::Replace { path, value } => {
+            remove(state, path)?;
+            add(state, path, value.0.clone())
+        }
+        PatchOperation::Move { from, path } => {
+            if path.starts_with(&format!("{}/", from)) {
+                return Err(PatchError::InvalidMove(from.clone(), path.clone()));
+            }
+            let value = remove(state, from)?;
+            add(state, path, value)
+        }
+        PatchOperation::Copy { from, path } => {
+            let value = resolve(state, from)?.clone();
+            add(state, path, value)
+        }
+        PatchOperation::Test { path, value } => {
+            if resolve(state, path)? == &value.0 {
+                Ok(())
+            } else {
+                Err(PatchError::TestFailed(path.clone()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_diff_and_apply() {
+        let from = json!({
+            "name": "subject",
+            "a/b": 1,
+            "m~n": [1, 2, 3],
+            "nested": { "keep": true, "drop": null },
+            "list": [{ "id": 1 }, { "id": 2 }]
+        });
+        let to = json!({
+            "name": "subject",
+            "a/b": 2,
+            "m~n": [1, 5],
+            "nested": { "keep": true, "new": "value" },
+            "list": [{ "id": 1 }, { "id": 2 }, { "id": 3 }]
+        });
+        let patch = diff(&from, &to);
+        let StatePatch::Patch(operations) = &patch else {
+            panic!("Expected a patch");
+        };
+        assert!(operations.contains(&PatchOperation::Replace {
+            path: "/a~1b".to_owned(),
+            value: ValueWrapper(json!(2)),
+        }));
+        assert!(operations.contains(&PatchOperation::Remove {
+            path: "/m~0n/2".to_owned(),
+        }));
+
+        let mut state = from.clone();
+        apply(&mut state, &patch).unwrap();
+        assert_eq!(state, to);
+        assert_eq!(diff(&to, &to), StatePatch::Unchanged);
+
+        let bytes = borsh::to_vec(&patch).unwrap();
+        assert_eq!(StatePatch::try_from_slice(&bytes).unwrap(), patch);
+    }
+
+    #[test]
+    fn test_rfc_operations() {
+        let mut state = json!({ "foo": ["bar", "baz"], "qux": { "a": 1 } });
+        let operations: Vec<PatchOperation> = serde_json::from_value(json!([
+            { "op": "add", "path": "/foo/1", "value": "qux" },
+            { "op": "add", "path": "/foo/-", "value": "end" },
+            { "op": "copy", "from": "/qux/a", "path": "/copied" },
+            { "op": "move", "from": "/qux", "path": "/moved" },
+            { "op": "test", "path": "/moved/a", "value": 1 }
+        ]))
+        .unwrap();
+        apply_operations(&mut state, &operations).unwrap();
+        assert_eq!(
+            state,
+            json!({ "foo": ["bar", "qux", "baz", "end"], "copied": 1, "moved": { "a": 1 } })
+        );
+    }
+
+    #[test]
+    fn test_failed_patch_is_atomic() {
+        let mut state = json!({ "value": 1 });
+        let operations = vec![
+            PatchOperation::Replace {
+                path: "/value".to_owned(),
+                value: ValueWrapper(json!(2)),
+            },
+            PatchOperation::Test {
+                path: "/value".to_owned(),
+                value: ValueWrapper(json!(3)),
+            },
+        ];
+        let error = apply_operations(&mut state, &operations).unwrap_err();
+        assert_eq!(error, PatchError::TestFailed("/value".to_owned()));
+        assert_eq!(state, json!({ "value": 1 }));
+
+        let error = apply_operations(
+            &mut state,
+            &[PatchOperation::Remove {
+                path: "/missing".to_owned(),
+            }],
+        )
+        .unwrap_err();
+        assert_eq!(error, PatchError::PathNotFound("/missing".to_owned()));
+    }
+}
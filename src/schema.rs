@@ -0,0 +1,108 @@
+// Copyright 2025 Kore Ledger
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! JSON Schema generation for the contract `State` and `Event` types, enabled with the
+//! `schema` feature.
+//!
+//! Types derive `JsonSchema`. Without a direct dependency on `schemars`, the derive must be
+//! pointed to the SDK re-export:
+//!
+//! ```ignore
+//! use kore_contract_sdk::schema::JsonSchema;
+//!
+//! #[derive(Serialize, Deserialize, Clone, JsonSchema)]
+//! #[schemars(crate = "kore_contract_sdk::schema::schemars")]
+//! struct State {
+//!     pub value: u32,
+//! }
+//! ```
+//!
+//! The schemas can be written from a test or build tool with `ContractSchema::write_to`, or
+//! exported from the wasm module with `#[contract(schema)]`, which adds a `schema_function`
+//! entry point returning the schemas to the host.
+
+use std::{fs, io, path::Path};
+
+pub use schemars::{self, JsonSchema};
+use serde_json::{Value, json};
+
+use crate::{ValueWrapper, store};
+
+/// JSON Schemas of the types used by a contract.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContractSchema {
+    /// Schema of the subject state, used as the governance schema.
+    pub state: Value,
+    /// Schema of the events accepted by the contract.
+    pub event: Value,
+}
+
+impl ContractSchema {
+    /// Generates the schemas of `State` and `Event`.
+    pub fn new<State: JsonSchema, Event: JsonSchema>() -> Self {
+        Self {
+            state: schemars::schema_for!(State).to_value(),
+            event: schemars::schema_for!(Event).to_value(),
+        }
+    }
+
+    /// Returns both schemas as `{ "state": .., "event": .. }`.
+    pub fn to_json(&self) -> Value {
+        json!({ "state": self.state, "event": self.event })
+    }
+
+    /// Writes `state.schema.json` and `event.schema.json` into `dir`, creating it if needed.
+    pub fn write_to(&self, dir: impl AsRef<Path>) -> io::Result<()> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join("state.schema.json"), pretty(&self.state)?)?;
+        fs::write(dir.join("event.schema.json"), pretty(&self.event)?)
+    }
+}
+
+fn pretty(value: &Value) -> io::Result<String> {
+    serde_json::to_string_pretty(value).map_err(io::Error::other)
+}
+
+/// Schema export.
+///
+/// # Returns
+///
+/// * `result_ptr` - Pointer to the `ValueWrapper` with the schemas as returned by
+///   `ContractSchema::to_json`.
+///
+pub fn export_schema<State: JsonSchema, Event: JsonSchema>() -> u32 {
+    let schema = ContractSchema::new::<State, Event>();
+    store(&ValueWrapper(schema.to_json())).expect("Contract store process failed")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, JsonSchema)]
+    struct State {
+        value: u32,
+        name: Option<String>,
+    }
+
+    #[derive(Serialize, Deserialize, JsonSchema)]
+    enum Event {
+        Set { value: u32 },
+        Reset,
+    }
+
+    #[test]
+    fn test_contract_schema() {
+        let schema = ContractSchema::new::<State, Event>();
+        assert_eq!(schema.state["type"], "object");
+        assert_eq!(schema.state["required"], json!(["value"]));
+        assert_eq!(schema.state["properties"]["value"]["type"], "integer");
+        assert!(schema.event["oneOf"].is_array());
+
+        let exported = crate::testing::get(export_schema::<State, Event>());
+        let exported: ValueWrapper = borsh::from_slice(&exported).unwrap();
+        assert_eq!(exported.0, schema.to_json());
+    }
+}
@@ -0,0 +1,805 @@
+// Copyright 2025 Kore Ledger
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Serde serialization straight into the `ValueWrapper` wire format.
+//!
+//! `to_bytes(&value)` gives the same bytes as the borsh encoding of
+//! `ValueWrapper(serde_json::to_value(&value)?)`, without building the `Value`: object keys are
+//! sorted, a repeated key keeps its last value and non-finite floats become `null`. Map keys
+//! must be strings, integers, booleans or unit variants, as in JSON, except that float keys
+//! are rejected.
+//!
+//! The only exceptions are the values of `crate::bytes` and the SDK number types when the
+//! serializer writes dedicated tags, see `Serializer::with_dedicated_tags`: byte arrays are
+//! written as raw bytes with the bytes tag instead of as their `{ "$bytes": "<base64>" }`
+//! object, and numbers with the numeric tag instead of the string tag. Other types that use
+//! `serialize_bytes`, e.g. those of `serde_bytes`, are written as arrays of numbers, like
+//! `serde_json` does.
+//!
+//! Integers that do not fit in 64 bits are written as number literals, and an `f32` as the
+//! `f64` of its shortest decimal form, as in the `Value` of serde_json with arbitrary
+//! precision numbers.
+
+use serde::ser::{self, Serialize};
+use serde_json::Value;
+
+use crate::{bytes, de::tag, error::EncodeError, numeric, value_wrapper::serialize_value};
+
+/// Struct name used by serde_json to pass arbitrary precision numbers through serde.
+const NUMBER_TOKEN: &str = "$serde_json::private::Number";
+
+/// Serializes `value` into its `ValueWrapper` borsh encoding.
+pub fn to_bytes<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, EncodeError> {
+    let mut serializer = Serializer::new();
+    value.serialize(&mut serializer)?;
+    Ok(serializer.into_bytes())
+}
+
+/// Serializer into the `ValueWrapper` borsh encoding.
+#[derive(Debug, Default)]
+pub struct Serializer {
+    output: Vec<u8>,
+    /// Whether the values of `crate::bytes` and the SDK number types are written with their
+    /// own tags.
+    dedicated_tags: bool,
+    /// Set while serializing a `crate::bytes` array, which then asks for raw bytes.
+    raw_bytes: bool,
+}
+
+impl Serializer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes the byte arrays of `crate::bytes` as raw bytes with the bytes tag, and `Decimal`,
+    /// `U256` and `I256` as their string with the numeric tag. Hosts with an SDK older than
+    /// these tags can not decode them, so they are only written on request.
+    pub fn with_dedicated_tags(mut self, dedicated_tags: bool) -> Self {
+        self.dedicated_tags = dedicated_tags;
+        self
+    }
+
+    /// Encoded bytes of the serialized values.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.output
+    }
+
+    fn write_str(&mut self, string: &str) {
+        self.write_str_bytes(string.as_bytes());
+    }
+
+    /// Writes a length prefixed byte string.
+    fn write_str_bytes(&mut self, bytes: &[u8]) {
+        self.output
+            .extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        self.output.extend_from_slice(bytes);
+    }
+
+    /// Writes a number, or `null` for non-finite floats, with the encoding of `ValueWrapper`.
+    fn write_value(&mut self, value: &Value) -> Result<(), EncodeError> {
+        serialize_value(value, &mut self.output)
+            .map_err(|error| EncodeError::Message(error.to_string()))
+    }
+
+    /// Writes an integer that does not fit in 64 bits as a number literal.
+    fn write_big_integer(&mut self, literal: String) -> Result<(), EncodeError> {
+        self.output.extend_from_slice(&[tag::NUMBER, tag::LITERAL]);
+        self.write_str(&literal);
+        Ok(())
+    }
+
+    /// Starts an object with a single entry, used for enum variants with content.
+    fn write_variant(&mut self, variant: &str) {
+        self.output.push(tag::OBJECT);
+        self.output.extend_from_slice(&1u32.to_le_bytes());
+        self.write_str(variant);
+    }
+
+    /// Serializes `value` into its own buffer, for object entries that are sorted later.
+    fn encode<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<Vec<u8>, EncodeError> {
+        let start = self.output.len();
+        value.serialize(&mut *self)?;
+        Ok(self.output.split_off(start))
+    }
+}
+
+impl<'a> ser::Serializer for &'a mut Serializer {
+    type Ok = ();
+    type Error = EncodeError;
+    type SerializeSeq = Seq<'a>;
+    type SerializeTuple = Seq<'a>;
+    type SerializeTupleStruct = Seq<'a>;
+    type SerializeTupleVariant = Seq<'a>;
+    type SerializeMap = Map<'a>;
+    type SerializeStruct = Struct<'a>;
+    type SerializeStructVariant = Map<'a>;
+
+    fn is_human_readable(&self) -> bool {
+        !self.raw_bytes
+    }
+
+    fn serialize_bool(self, value: bool) -> Result<(), EncodeError> {
+        self.output.extend_from_slice(&[tag::BOOL, value as u8]);
+        Ok(())
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<(), EncodeError> {
+        self.serialize_i64(value.into())
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<(), EncodeError> {
+        self.serialize_i64(value.into())
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<(), EncodeError> {
+        self.serialize_i64(value.into())
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<(), EncodeError> {
+        self.write_value(&Value::from(value))
+    }
+
+    fn serialize_i128(self, value: i128) -> Result<(), EncodeError> {
+        if let Ok(value) = i64::try_from(value) {
+            self.serialize_i64(value)
+        } else if let Ok(value) = u64::try_from(value) {
+            self.serialize_u64(value)
+        } else {
+            self.write_big_integer(value.to_string())
+        }
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<(), EncodeError> {
+        self.serialize_i64(value.into())
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<(), EncodeError> {
+        self.serialize_i64(value.into())
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<(), EncodeError> {
+        self.serialize_i64(value.into())
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<(), EncodeError> {
+        self.write_value(&Value::from(value))
+    }
+
+    fn serialize_u128(self, value: u128) -> Result<(), EncodeError> {
+        match u64::try_from(value) {
+            Ok(value) => self.serialize_u64(value),
+            Err(_) => self.write_big_integer(value.to_string()),
+        }
+    }
+
+    fn serialize_f32(self, value: f32) -> Result<(), EncodeError> {
+        self.write_value(&Value::from(value))
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<(), EncodeError> {
+        self.write_value(&Value::from(value))
+    }
+
+    fn serialize_char(self, value: char) -> Result<(), EncodeError> {
+        self.serialize_str(value.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, value: &str) -> Result<(), EncodeError> {
+        self.output.push(tag::STRING);
+        self.write_str(value);
+        Ok(())
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<(), EncodeError> {
+        if self.raw_bytes {
+            self.output.push(tag::BYTES);
+            self.write_str_bytes(value);
+            return Ok(());
+        }
+        // As in `serde_json`, so the state reads back through its `Value`.
+        let mut seq = ser::Serializer::serialize_seq(self, Some(value.len()))?;
+        for byte in value {
+            ser::SerializeSeq::serialize_element(&mut seq, byte)?;
+        }
+        ser::SerializeSeq::end(seq)
+    }
+
+    fn serialize_none(self) -> Result<(), EncodeError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), EncodeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), EncodeError> {
+        self.output.push(tag::NULL);
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), EncodeError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), EncodeError> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<(), EncodeError> {
+        if name == bytes::TOKEN && self.dedicated_tags {
+            self.raw_bytes = true;
+            let result = value.serialize(&mut *self);
+            self.raw_bytes = false;
+            return result;
+        }
+        if name == numeric::TOKEN && self.dedicated_tags {
+            let literal = value.serialize(KeySerializer)?;
+            self.output.push(tag::NUMERIC);
+            self.write_str(&literal);
+            return Ok(());
+        }
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), EncodeError> {
+        self.write_variant(variant);
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Seq<'a>, EncodeError> {
+        Ok(Seq::new(self))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Seq<'a>, EncodeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Seq<'a>, EncodeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Seq<'a>, EncodeError> {
+        self.write_variant(variant);
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Map<'a>, EncodeError> {
+        Ok(Map::new(self))
+    }
+
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<Struct<'a>, EncodeError> {
+        if name == NUMBER_TOKEN {
+            return Ok(Struct::Number(self));
+        }
+        self.serialize_map(Some(len)).map(Struct::Map)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Map<'a>, EncodeError> {
+        self.write_variant(variant);
+        self.serialize_map(Some(len))
+    }
+}
+
+/// Array whose length is written once all its elements are serialized.
+pub struct Seq<'a> {
+    serializer: &'a mut Serializer,
+    len_at: usize,
+    len: u32,
+}
+
+impl<'a> Seq<'a> {
+    fn new(serializer: &'a mut Serializer) -> Self {
+        serializer.output.push(tag::ARRAY);
+        let len_at = serializer.output.len();
+        serializer.output.extend_from_slice(&[0; 4]);
+        Self {
+            serializer,
+            len_at,
+            len: 0,
+        }
+    }
+
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EncodeError> {
+        value.serialize(&mut *self.serializer)?;
+        self.len += 1;
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), EncodeError> {
+        self.serializer.output[self.len_at..self.len_at + 4]
+            .copy_from_slice(&self.len.to_le_bytes());
+        Ok(())
+    }
+}
+
+impl ser::SerializeSeq for Seq<'_> {
+    type Ok = ();
+    type Error = EncodeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EncodeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<(), EncodeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for Seq<'_> {
+    type Ok = ();
+    type Error = EncodeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EncodeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<(), EncodeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for Seq<'_> {
+    type Ok = ();
+    type Error = EncodeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EncodeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<(), EncodeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for Seq<'_> {
+    type Ok = ();
+    type Error = EncodeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EncodeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<(), EncodeError> {
+        self.finish()
+    }
+}
+
+/// Object whose entries are buffered so they can be written sorted by key.
+pub struct Map<'a> {
+    serializer: &'a mut Serializer,
+    entries: Vec<(String, Vec<u8>)>,
+    key: Option<String>,
+}
+
+impl<'a> Map<'a> {
+    fn new(serializer: &'a mut Serializer) -> Self {
+        Self {
+            serializer,
+            entries: Vec::new(),
+            key: None,
+        }
+    }
+
+    fn insert<T: Serialize + ?Sized>(&mut self, key: String, value: &T) -> Result<(), EncodeError> {
+        let value = self.serializer.encode(value)?;
+        self.entries.push((key, value));
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<(), EncodeError> {
+        // The sort is stable, so the last value of a repeated key is the one kept.
+        self.entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        self.entries.dedup_by(|later, earlier| {
+            let repeated = later.0 == earlier.0;
+            if repeated {
+                std::mem::swap(later, earlier);
+            }
+            repeated
+        });
+        let len = self.entries.len() as u32;
+        self.serializer.output.push(tag::OBJECT);
+        self.serializer.output.extend_from_slice(&len.to_le_bytes());
+        for (key, value) in &self.entries {
+            self.serializer.write_str(key);
+            self.serializer.output.extend_from_slice(value);
+        }
+        Ok(())
+    }
+}
+
+impl ser::SerializeMap for Map<'_> {
+    type Ok = ();
+    type Error = EncodeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), EncodeError> {
+        self.key = Some(key.serialize(KeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EncodeError> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| EncodeError::Message("Map value without a key".to_owned()))?;
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<(), EncodeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for Map<'_> {
+    type Ok = ();
+    type Error = EncodeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), EncodeError> {
+        self.insert(key.to_owned(), value)
+    }
+
+    fn end(self) -> Result<(), EncodeError> {
+        self.finish()
+    }
+}
+
+/// Struct, serialized as an object, or a serde_json number.
+pub enum Struct<'a> {
+    Map(Map<'a>),
+    Number(&'a mut Serializer),
+}
+
+impl ser::SerializeStruct for Struct<'_> {
+    type Ok = ();
+    type Error = EncodeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), EncodeError> {
+        match self {
+            Struct::Map(map) => map.insert(key.to_owned(), value),
+            Struct::Number(serializer) => {
+                let literal = value.serialize(KeySerializer)?;
+                let number = literal
+                    .parse::<serde_json::Number>()
+                    .map_err(|_| EncodeError::Message(format!("Invalid number: {}", literal)))?;
+                serializer.write_value(&Value::Number(number))
+            }
+        }
+    }
+
+    fn end(self) -> Result<(), EncodeError> {
+        match self {
+            Struct::Map(map) => map.finish(),
+            Struct::Number(_) => Ok(()),
+        }
+    }
+}
+
+/// Serializes object keys into strings.
+struct KeySerializer;
+
+impl KeySerializer {
+    fn integer<T: ToString>(value: T) -> Result<String, EncodeError> {
+        Ok(value.to_string())
+    }
+}
+
+impl ser::Serializer for KeySerializer {
+    type Ok = String;
+    type Error = EncodeError;
+    type SerializeSeq = ser::Impossible<String, EncodeError>;
+    type SerializeTuple = ser::Impossible<String, EncodeError>;
+    type SerializeTupleStruct = ser::Impossible<String, EncodeError>;
+    type SerializeTupleVariant = ser::Impossible<String, EncodeError>;
+    type SerializeMap = ser::Impossible<String, EncodeError>;
+    type SerializeStruct = ser::Impossible<String, EncodeError>;
+    type SerializeStructVariant = ser::Impossible<String, EncodeError>;
+
+    fn serialize_bool(self, value: bool) -> Result<String, EncodeError> {
+        Ok(value.to_string())
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<String, EncodeError> {
+        Self::integer(value)
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<String, EncodeError> {
+        Self::integer(value)
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<String, EncodeError> {
+        Self::integer(value)
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<String, EncodeError> {
+        Self::integer(value)
+    }
+
+    fn serialize_i128(self, value: i128) -> Result<String, EncodeError> {
+        Self::integer(value)
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<String, EncodeError> {
+        Self::integer(value)
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<String, EncodeError> {
+        Self::integer(value)
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<String, EncodeError> {
+        Self::integer(value)
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<String, EncodeError> {
+        Self::integer(value)
+    }
+
+    fn serialize_u128(self, value: u128) -> Result<String, EncodeError> {
+        Self::integer(value)
+    }
+
+    fn serialize_f32(self, _value: f32) -> Result<String, EncodeError> {
+        Err(EncodeError::KeyMustBeString)
+    }
+
+    fn serialize_f64(self, _value: f64) -> Result<String, EncodeError> {
+        Err(EncodeError::KeyMustBeString)
+    }
+
+    fn serialize_char(self, value: char) -> Result<String, EncodeError> {
+        Ok(value.to_string())
+    }
+
+    fn serialize_str(self, value: &str) -> Result<String, EncodeError> {
+        Ok(value.to_owned())
+    }
+
+    fn serialize_bytes(self, _value: &[u8]) -> Result<String, EncodeError> {
+        Err(EncodeError::KeyMustBeString)
+    }
+
+    fn serialize_none(self) -> Result<String, EncodeError> {
+        Err(EncodeError::KeyMustBeString)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<String, EncodeError> {
+        Err(EncodeError::KeyMustBeString)
+    }
+
+    fn serialize_unit(self) -> Result<String, EncodeError> {
+        Err(EncodeError::KeyMustBeString)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String, EncodeError> {
+        Err(EncodeError::KeyMustBeString)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<String, EncodeError> {
+        Ok(variant.to_owned())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<String, EncodeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String, EncodeError> {
+        Err(EncodeError::KeyMustBeString)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, EncodeError> {
+        Err(EncodeError::KeyMustBeString)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, EncodeError> {
+        Err(EncodeError::KeyMustBeString)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, EncodeError> {
+        Err(EncodeError::KeyMustBeString)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, EncodeError> {
+        Err(EncodeError::KeyMustBeString)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, EncodeError> {
+        Err(EncodeError::KeyMustBeString)
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, EncodeError> {
+        Err(EncodeError::KeyMustBeString)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, EncodeError> {
+        Err(EncodeError::KeyMustBeString)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use super::*;
+    use crate::{Decimal, U256, ValueWrapper, de::from_bytes};
+    use borsh::BorshDeserialize;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Kind {
+        Unit,
+        Newtype(u8),
+        Tuple(i32, String),
+        Struct { b: bool, a: Option<f64> },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct State {
+        name: String,
+        kinds: Vec<Kind>,
+        balances: BTreeMap<u32, Decimal<2>>,
+        total: U256,
+        ratio: f32,
+        big: u64,
+        negative: i128,
+        unit: (),
+        pair: (char, i8),
+    }
+
+    fn wire<T: Serialize>(value: &T) -> Vec<u8> {
+        borsh::to_vec(&ValueWrapper(serde_json::to_value(value).unwrap())).unwrap()
+    }
+
+    #[test]
+    fn test_to_bytes() {
+        let state = State {
+            name: "subject".to_owned(),
+            kinds: vec![
+                Kind::Unit,
+                Kind::Newtype(7),
+                Kind::Tuple(-1, "x".to_owned()),
+                Kind::Struct {
+                    b: true,
+                    a: Some(0.5),
+                },
+            ],
+            balances: BTreeMap::from([(10, "1.25".parse().unwrap()), (2, Decimal::ZERO)]),
+            total: "115792089237316195423570985008687907853269984665640564039457"
+                .parse()
+                .unwrap(),
+            ratio: 0.25,
+            big: u64::MAX,
+            negative: -3,
+            unit: (),
+            pair: ('ñ', -8),
+        };
+        let bytes = to_bytes(&state).unwrap();
+        assert_eq!(bytes, wire(&state));
+        assert_eq!(from_bytes::<State>(&bytes).unwrap(), state);
+
+        let values = (
+            f64::NAN,
+            json_value(),
+            HashMap::from([(true, 1), (false, 2)]),
+        );
+        assert_eq!(to_bytes(&values).unwrap(), wire(&values));
+    }
+
+    fn json_value() -> serde_json::Value {
+        serde_json::json!({ "z": [1, 2], "a": null, "m": { "k": -1.5 } })
+    }
+
+    #[test]
+    fn test_wire_format() {
+        let values = (1.1f32, u128::MAX, "-1.5".parse::<Decimal<2>>().unwrap());
+        let mut expected = vec![3, 3, 0, 0, 0, 1, 0];
+        expected.extend(1.1f64.to_le_bytes());
+        expected.extend([1, 3, 39, 0, 0, 0]);
+        expected.extend(b"340282366920938463463374607431768211455");
+        expected.extend([2, 4, 0, 0, 0]);
+        expected.extend(b"-1.5");
+        let bytes = to_bytes(&values).unwrap();
+        assert_eq!(bytes, expected);
+        assert_eq!(bytes, wire(&values));
+        assert_eq!(
+            from_bytes::<(f32, u128, Decimal<2>)>(&bytes).unwrap(),
+            values
+        );
+
+        let value = ValueWrapper::try_from_slice(&bytes).unwrap();
+        assert_eq!(value.0, serde_json::json!([1.1f32, u128::MAX, "-1.5"]));
+
+        // The decimal gets the numeric tag, and both decoders give the same values.
+        let mut serializer = Serializer::new().with_dedicated_tags(true);
+        values.serialize(&mut serializer).unwrap();
+        let tagged = serializer.into_bytes();
+        assert!(tagged.ends_with(&[7, 4, 0, 0, 0, b'-', b'1', b'.', b'5']));
+        assert_eq!(
+            from_bytes::<(f32, u128, Decimal<2>)>(&tagged).unwrap(),
+            values
+        );
+        assert_eq!(ValueWrapper::try_from_slice(&tagged).unwrap(), value);
+        assert_eq!(from_bytes::<serde_json::Value>(&tagged).unwrap(), value.0);
+
+        let (_, big, _): (f32, U256, Decimal<2>) = serde_json::from_value(value.0).unwrap();
+        assert_eq!(big, U256::from(u128::MAX));
+    }
+
+    #[test]
+    fn test_to_bytes_errors() {
+        let map = HashMap::from([((1, 2), 3)]);
+        assert_eq!(to_bytes(&map), Err(EncodeError::KeyMustBeString));
+    }
+}
@@ -0,0 +1,432 @@
+// Copyright 2025 Kore Ledger
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Declarative state machines for subject lifecycles.
+//!
+//! The lifecycle status is read from and written to the contract state, and each event is
+//! identified by its name. Only the declared transitions are accepted: any other event is
+//! rejected with a `codes::ILLEGAL_TRANSITION` error.
+//!
+//! ```ignore
+//! fn lifecycle() -> StateMachine<State, Event, Status> {
+//!     StateMachine::new(
+//!         |state: &State| state.status,
+//!         |state: &mut State, status| state.status = status,
+//!         Event::name,
+//!     )
+//!         .transition(Status::Draft, "Approve", Status::Approved)
+//!         .guard(|context| match context.is_owner {
+//!             true => Ok(()),
+//!             false => Err(ContractError::authorization(codes::USER, "Not the owner")),
+//!         })
+//!         .transition(Status::Approved, "Close", Status::Closed)
+//!         .effect(|context, state| {
+//!             state.closed_at = context.timestamp;
+//!             Ok(())
+//!         })
+//! }
+//!
+//! #[contract]
+//! fn contract_logic(context: &Context<State, Event>, result: &mut ContractResult<State>) {
+//!     lifecycle().execute(context, result)
+//! }
+//! ```
+
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::{Context, ContractError, ContractResult, codes};
+
+/// Condition that must hold for a transition to be taken.
+pub type Guard<State, Event> = fn(&Context<State, Event>) -> Result<(), ContractError>;
+
+/// Changes applied to the state when a transition is taken, before the new status is set.
+/// If the effect fails, none of its changes are kept.
+pub type Effect<State, Event> = fn(&Context<State, Event>, &mut State) -> Result<(), ContractError>;
+
+/// Allowed transition between two statuses.
+pub struct Transition<State, Event, Status> {
+    /// Status the subject must be in.
+    pub from: Status,
+    /// Name of the event that triggers the transition.
+    pub event: &'static str,
+    /// Status of the subject after the transition.
+    pub to: Status,
+    guard: Option<Guard<State, Event>>,
+    effect: Option<Effect<State, Event>>,
+}
+
+impl<State, Event, Status: Clone> Clone for Transition<State, Event, Status> {
+    fn clone(&self) -> Self {
+        Self {
+            from: self.from.clone(),
+            event: self.event,
+            to: self.to.clone(),
+            guard: self.guard,
+            effect: self.effect,
+        }
+    }
+}
+
+/// Lifecycle of a subject declared as statuses and transitions.
+pub struct StateMachine<State, Event, Status> {
+    status: fn(&State) -> Status,
+    set_status: fn(&mut State, Status),
+    event_name: fn(&Event) -> &'static str,
+    states: Vec<Status>,
+    transitions: Vec<Transition<State, Event, Status>>,
+}
+
+impl<State, Event, Status: Clone> Clone for StateMachine<State, Event, Status> {
+    fn clone(&self) -> Self {
+        Self {
+            status: self.status,
+            set_status: self.set_status,
+            event_name: self.event_name,
+            states: self.states.clone(),
+            transitions: self.transitions.clone(),
+        }
+    }
+}
+
+impl<State, Event, Status> StateMachine<State, Event, Status>
+where
+    Status: Clone + PartialEq + Serialize,
+{
+    /// Creates a state machine without transitions.
+    ///
+    /// `status` and `set_status` read and write the lifecycle status of the state, and
+    /// `event_name` names the events used to declare the transitions.
+    pub fn new(
+        status: fn(&State) -> Status,
+        set_status: fn(&mut State, Status),
+        event_name: fn(&Event) -> &'static str,
+    ) -> Self {
+        Self {
+            status,
+            set_status,
+            event_name,
+            states: Vec::new(),
+            transitions: Vec::new(),
+        }
+    }
+
+    /// Declares a status. Statuses used in transitions are declared automatically, so this
+    /// is only needed for statuses without transitions.
+    pub fn state(mut self, status: Status) -> Self {
+        if !self.states.contains(&status) {
+            self.states.push(status);
+        }
+        self
+    }
+
+    /// Allows `event` to move the subject from `from` to `to`.
+    pub fn transition(mut self, from: Status, event: &'static str, to: Status) -> Self {
+        self = self.state(from.clone()).state(to.clone());
+        self.transitions.push(Transition {
+            from,
+            event,
+            to,
+            guard: None,
+            effect: None,
+        });
+        self
+    }
+
+    /// Sets the guard of the last declared transition.
+    ///
+    /// # Panics
+    ///
+    /// If no transition has been declared.
+    pub fn guard(mut self, guard: Guard<State, Event>) -> Self {
+        self.last_transition().guard = Some(guard);
+        self
+    }
+
+    /// Sets the effect of the last declared transition.
+    ///
+    /// # Panics
+    ///
+    /// If no transition has been declared.
+    pub fn effect(mut self, effect: Effect<State, Event>) -> Self {
+        self.last_transition().effect = Some(effect);
+        self
+    }
+
+    /// Declared statuses.
+    pub fn states(&self) -> &[Status] {
+        &self.states
+    }
+
+    /// Declared transitions.
+    pub fn transitions(&self) -> &[Transition<State, Event, Status>] {
+        &self.transitions
+    }
+
+    /// Transition taken by `event` from `status`, if any.
+    pub fn find(
+        &self,
+        status: &Status,
+        event: &Event,
+    ) -> Option<&Transition<State, Event, Status>> {
+        let event = (self.event_name)(event);
+        self.transitions
+            .iter()
+            .find(|transition| transition.from == *status && transition.event == event)
+    }
+
+    /// Contract logic of the state machine, to be called from the contract callback.
+    ///
+    /// Rejects events without a transition from the current status, then runs the guard and
+    /// the effect of the transition and sets the new status on the final state. The final
+    /// state is left untouched if the transition is rejected or its effect fails.
+    pub fn execute(&self, context: &Context<State, Event>, result: &mut ContractResult<State>)
+    where
+        State: Clone,
+    {
+        match self.apply(context, &mut result.final_state) {
+            Ok(()) => result.success = true,
+            Err(error) => {
+                result.success = false;
+                result.error = Some(error);
+            }
+        }
+    }
+
+    fn apply(&self, context: &Context<State, Event>, state: &mut State) -> Result<(), ContractError>
+    where
+        State: Clone,
+    {
+        let status = (self.status)(&context.initial_state);
+        let Some(transition) = self.find(&status, &context.event) else {
+            let event = (self.event_name)(&context.event);
+            return Err(ContractError::invalid_event(
+                codes::ILLEGAL_TRANSITION,
+                format!(
+                    "Event {} is not allowed in state {}",
+                    event,
+                    status_name(&status)
+                ),
+            )
+            .with_details(json!({ "state": status_name(&status), "event": event })));
+        };
+        if let Some(guard) = transition.guard {
+            guard(context)?;
+        }
+        if let Some(effect) = transition.effect {
+            // The effect runs on a copy, so a failure halfway does not leak its changes.
+            let mut changed = state.clone();
+            effect(context, &mut changed)?;
+            *state = changed;
+        }
+        (self.set_status)(state, transition.to.clone());
+        Ok(())
+    }
+
+    /// Transition graph in Graphviz DOT format. Guarded transitions are dashed.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph lifecycle {\n");
+        for status in &self.states {
+            dot.push_str(&format!("    {};\n", quote(&status_name(status))));
+        }
+        for transition in &self.transitions {
+            let style = if transition.guard.is_some() {
+                ", style=dashed"
+            } else {
+                ""
+            };
+            dot.push_str(&format!(
+                "    {} -> {} [label={}{}];\n",
+                quote(&status_name(&transition.from)),
+                quote(&status_name(&transition.to)),
+                quote(transition.event),
+                style
+            ));
+        }
+        dot.push('}');
+        dot
+    }
+
+    /// Transition graph as `{ "states": [..], "transitions": [{ "from", "event", "to",
+    /// "guarded", "effect" }] }`.
+    pub fn to_json(&self) -> Value {
+        let transitions: Vec<Value> = self
+            .transitions
+            .iter()
+            .map(|transition| {
+                json!({
+                    "from": status_name(&transition.from),
+                    "event": transition.event,
+                    "to": status_name(&transition.to),
+                    "guarded": transition.guard.is_some(),
+                    "effect": transition.effect.is_some(),
+                })
+            })
+            .collect();
+        let states: Vec<String> = self.states.iter().map(status_name).collect();
+        json!({ "states": states, "transitions": transitions })
+    }
+
+    fn last_transition(&mut self) -> &mut Transition<State, Event, Status> {
+        self.transitions
+            .last_mut()
+            .expect("A transition must be declared first")
+    }
+}
+
+/// Name of a status, as serialized by serde for unit enum variants.
+fn status_name<Status: Serialize>(status: &Status) -> String {
+    match serde_json::to_value(status) {
+        Ok(Value::String(name)) => name,
+        Ok(value) => value.to_string(),
+        Err(_) => String::new(),
+    }
+}
+
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ErrorCategory, testing::Invocation};
+    use serde::Deserialize;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    enum Status {
+        Draft,
+        Approved,
+        Closed,
+        Archived,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct State {
+        status: Status,
+        closed_at: u64,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    enum Event {
+        Approve,
+        Close,
+    }
+
+    fn event_name(event: &Event) -> &'static str {
+        match event {
+            Event::Approve => "Approve",
+            Event::Close => "Close",
+        }
+    }
+
+    fn lifecycle() -> StateMachine<State, Event, Status> {
+        StateMachine::new(
+            |state: &State| state.status,
+            |state: &mut State, status| state.status = status,
+            event_name,
+        )
+        .transition(Status::Draft, "Approve", Status::Approved)
+        .guard(|context| match context.is_owner {
+            true => Ok(()),
+            false => Err(ContractError::authorization(codes::USER, "Not the owner")),
+        })
+        .transition(Status::Approved, "Close", Status::Closed)
+        .effect(|context, state| {
+            state.closed_at = context.timestamp;
+            Ok(())
+        })
+        .state(Status::Archived)
+    }
+
+    fn contract_logic(context: &Context<State, Event>, result: &mut ContractResult<State>) {
+        lifecycle().execute(context, result)
+    }
+
+    fn state(status: Status) -> State {
+        State {
+            status,
+            closed_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_transitions() {
+        let result = Invocation::new(state(Status::Draft), Event::Approve)
+            .owner(true)
+            .run(contract_logic);
+        assert_eq!(result.final_state, Some(state(Status::Approved)));
+
+        let result = Invocation::new(state(Status::Draft), Event::Approve).run(contract_logic);
+        assert_eq!(result.error.unwrap().category, ErrorCategory::Authorization);
+
+        let result = Invocation::new(state(Status::Approved), Event::Close)
+            .timestamp(7)
+            .run(contract_logic);
+        let expected = State {
+            status: Status::Closed,
+            closed_at: 7,
+        };
+        assert_eq!(result.final_state, Some(expected));
+
+        let result = Invocation::new(state(Status::Draft), Event::Close).run(contract_logic);
+        let error = result.error.unwrap();
+        assert!(!result.success);
+        assert_eq!(error.code, codes::ILLEGAL_TRANSITION);
+        assert_eq!(error.category, ErrorCategory::InvalidEvent);
+        assert_eq!(error.message, "Event Close is not allowed in state Draft");
+    }
+
+    #[test]
+    fn test_failing_effect() {
+        let machine = StateMachine::new(
+            |state: &State| state.status,
+            |state: &mut State, status| state.status = status,
+            event_name,
+        )
+        .transition(Status::Approved, "Close", Status::Closed)
+        .effect(|context, state| {
+            state.closed_at = context.timestamp;
+            Err(ContractError::validation(
+                codes::USER,
+                "Closing is disabled",
+            ))
+        });
+        let context = Context {
+            initial_state: state(Status::Approved),
+            event: Event::Close,
+            is_owner: true,
+            signer: String::new(),
+            subject_id: String::new(),
+            governance_id: String::new(),
+            sn: 1,
+            timestamp: 7,
+        };
+        let mut result = ContractResult::new(state(Status::Approved));
+        machine.execute(&context, &mut result);
+        assert!(!result.success);
+        assert_eq!(result.error.unwrap().message, "Closing is disabled");
+        assert_eq!(result.final_state, state(Status::Approved));
+    }
+
+    #[test]
+    fn test_export() {
+        let machine = lifecycle();
+        assert_eq!(
+            machine.to_dot(),
+            "digraph lifecycle {\n    \"Draft\";\n    \"Approved\";\n    \"Closed\";\n    \
+             \"Archived\";\n    \"Draft\" -> \"Approved\" [label=\"Approve\", style=dashed];\n    \
+             \"Approved\" -> \"Closed\" [label=\"Close\"];\n}"
+        );
+        assert_eq!(
+            machine.to_json(),
+            json!({
+                "states": ["Draft", "Approved", "Closed", "Archived"],
+                "transitions": [
+                    { "from": "Draft", "event": "Approve", "to": "Approved", "guarded": true, "effect": false },
+                    { "from": "Approved", "event": "Close", "to": "Closed", "guarded": false, "effect": true }
+                ]
+            })
+        );
+    }
+}
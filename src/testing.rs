@@ -0,0 +1,616 @@
+// Copyright 2025 Kore Ledger
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Native mock of the Kore host, enabled with the `testing` feature.
+//!
+//! The host functions used by the SDK are backed by a thread local memory arena, so
+//! `execute_contract` and `check_init_data` can run inside `cargo test` through the same
+//! borsh/`ValueWrapper` transport used by the wasm runtime. This module must not be enabled
+//! when building the contract for the Kore runtime.
+
+use borsh::BorshDeserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    BatchMode, ContextInfoBorsh, ContractBatchResultBorsh, ContractError, ContractInitCheck,
+    ContractInitCheckBorsh, ContractInitResultBorsh, ContractLogic, ContractPatchResultBorsh,
+    ContractQueryResultBorsh, ContractResultBorsh, EventResult, ExecutionOptions, InitContext,
+    InitInfoBorsh, InitLogic, ResultMode, StatePatch, ValueWrapper, check_init_data,
+    execute_contract_batch, execute_contract_with, execute_query, init_contract_with, migration,
+    patch,
+};
+
+/// Mock implementation of the host functions imported by the SDK.
+pub(crate) mod host {
+    use std::{cell::RefCell, collections::BTreeMap};
+
+    /// Memory of the mock host. Each allocation is a contiguous region of `memory`.
+    #[derive(Default)]
+    struct Arena {
+        memory: Vec<u8>,
+        allocations: BTreeMap<i32, i32>,
+    }
+
+    impl Arena {
+        fn alloc(&mut self, len: u32) -> i32 {
+            let pointer = i32::try_from(self.memory.len()).expect("Mock host memory exhausted");
+            let len = i32::try_from(len).expect("Mock host allocation too large");
+            self.memory.resize(self.memory.len() + len as usize, 0);
+            self.allocations.insert(pointer, len);
+            pointer
+        }
+
+        fn len(&self, pointer: i32) -> i32 {
+            *self
+                .allocations
+                .get(&pointer)
+                .unwrap_or_else(|| panic!("Unknown mock host pointer: {}", pointer))
+        }
+    }
+
+    thread_local! {
+        static ARENA: RefCell<Arena> = RefCell::new(Arena::default());
+        /// Lines written through `cout` while captured, see `capture_output`.
+        static OUTPUT: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+    }
+
+    #[allow(dead_code)]
+    pub(crate) unsafe fn read_byte(pointer: i32) -> u8 {
+        ARENA.with_borrow(|arena| arena.memory[pointer as usize])
+    }
+
+    #[allow(dead_code)]
+    pub(crate) unsafe fn read_bytes(pointer: i32, dest: *mut u8, len: i32) {
+        ARENA.with_borrow(|arena| {
+            assert!(
+                len <= arena.len(pointer),
+                "Read out of bounds of mock host pointer {}",
+                pointer
+            );
+            let start = pointer as usize;
+            let source = &arena.memory[start..start + len as usize];
+            unsafe { std::ptr::copy_nonoverlapping(source.as_ptr(), dest, source.len()) };
+        })
+    }
+
+    pub(crate) unsafe fn pointer_len(pointer: i32) -> i32 {
+        ARENA.with_borrow(|arena| arena.len(pointer))
+    }
+
+    pub(crate) unsafe fn alloc(len: u32) -> i32 {
+        ARENA.with_borrow_mut(|arena| arena.alloc(len))
+    }
+
+    #[allow(dead_code)]
+    pub(crate) unsafe fn write_byte(ptr: u32, offset: u32, data: u8) {
+        ARENA.with_borrow_mut(|arena| {
+            let len = arena.len(ptr as i32) as u32;
+            assert!(
+                offset < len,
+                "Write out of bounds of mock host pointer {}",
+                ptr
+            );
+            arena.memory[(ptr + offset) as usize] = data;
+        })
+    }
+
+    #[allow(dead_code)]
+    pub(crate) unsafe fn write_bytes(ptr: u32, src: *const u8, len: u32) {
+        ARENA.with_borrow_mut(|arena| {
+            let allocation = arena.len(ptr as i32) as u32;
+            assert!(
+                len <= allocation,
+                "Write out of bounds of mock host pointer {}",
+                ptr
+            );
+            let start = ptr as usize;
+            let source = unsafe { std::slice::from_raw_parts(src, len as usize) };
+            arena.memory[start..start + len as usize].copy_from_slice(source);
+        })
+    }
+
+    pub(crate) unsafe fn cout(ptr: u32) {
+        let line = String::from_utf8_lossy(&super::get(ptr)).into_owned();
+        OUTPUT.with_borrow_mut(|output| match output {
+            Some(lines) => lines.push(line),
+            None => eprintln!("{}", line),
+        });
+    }
+
+    pub(super) fn capture_output<R>(f: impl FnOnce() -> R) -> (R, Vec<String>) {
+        let outer = OUTPUT.replace(Some(Vec::new()));
+        let result = f();
+        let lines = OUTPUT.replace(outer).unwrap_or_default();
+        (result, lines)
+    }
+
+    pub(super) fn put(bytes: &[u8]) -> i32 {
+        ARENA.with_borrow_mut(|arena| {
+            let pointer = arena.alloc(bytes.len() as u32);
+            let start = pointer as usize;
+            arena.memory[start..start + bytes.len()].copy_from_slice(bytes);
+            pointer
+        })
+    }
+
+    pub(super) fn get(pointer: u32) -> Vec<u8> {
+        ARENA.with_borrow(|arena| {
+            let start = pointer as usize;
+            let len = arena.len(pointer as i32) as usize;
+            arena.memory[start..start + len].to_vec()
+        })
+    }
+
+    pub(super) fn reset() {
+        ARENA.with_borrow_mut(|arena| *arena = Arena::default());
+    }
+}
+
+/// Copies `bytes` into the mock host memory and returns its pointer.
+pub fn put(bytes: &[u8]) -> i32 {
+    host::put(bytes)
+}
+
+/// Returns a copy of the mock host allocation starting at `pointer`.
+///
+/// # Panics
+///
+/// Panics if `pointer` was not returned by the mock host.
+pub fn get(pointer: u32) -> Vec<u8> {
+    host::get(pointer)
+}
+
+/// Releases all the memory of the mock host of the current thread.
+pub fn reset() {
+    host::reset()
+}
+
+/// Runs `f` and returns the lines written through `cout` and the log records, instead of
+/// printing them to stderr.
+///
+/// Panic reports are not captured: they only go through `cout` on wasm. Natively the panic
+/// description is the message of the `codes::CONTRACT_PANIC` error of the result.
+pub fn capture_output<R>(f: impl FnOnce() -> R) -> (R, Vec<String>) {
+    host::capture_output(f)
+}
+
+/// Encodes `value` with the `ValueWrapper` wire format and copies it into the mock host memory.
+///
+/// # Panics
+///
+/// Panics if `value` can not be represented as a `ValueWrapper`.
+pub fn put_value<T: Serialize>(value: &T) -> i32 {
+    let value = serde_json::to_value(value).expect("Can not convert value into Value");
+    let bytes = borsh::to_vec(&ValueWrapper(value)).expect("Can not serialize value");
+    put(&bytes)
+}
+
+/// Typed view of the result returned by `execute_contract`.
+#[derive(Debug)]
+pub struct ExecutionResult<State> {
+    /// Final state of the contract, `None` if the SDK could not produce one.
+    pub final_state: Option<State>,
+    /// Is the contract execution successful?
+    pub success: bool,
+    /// Contract error
+    pub error: Option<ContractError>,
+    /// Patch returned by the contract in `ResultMode::Patch`.
+    pub patch: Option<StatePatch>,
+    /// Output events emitted by the contract.
+    pub outputs: Vec<Value>,
+}
+
+/// Data of an invocation other than its events, shared by `Invocation` and
+/// `BatchInvocation`.
+struct Context<State, Event> {
+    state: State,
+    init_state: Option<State>,
+    is_owner: bool,
+    info: ContextInfoBorsh,
+    version: Option<u32>,
+    options: ExecutionOptions<State, Event>,
+}
+
+impl<State, Event> Context<State, Event>
+where
+    State: for<'a> Deserialize<'a> + Serialize + Clone,
+{
+    fn new(state: State) -> Self {
+        Self {
+            state,
+            init_state: None,
+            is_owner: false,
+            info: ContextInfoBorsh::default(),
+            version: None,
+            options: ExecutionOptions::default(),
+        }
+    }
+
+    /// Copies the state and the init state into the mock host memory.
+    fn put_states(&self) -> (i32, i32) {
+        let init_state = self.init_state.as_ref().unwrap_or(&self.state);
+        let state_ptr = put_value(&stored_value(&self.state, self.version));
+        let init_state_ptr = put_value(&stored_value(init_state, None));
+        (state_ptr, init_state_ptr)
+    }
+
+    /// Typed state from a value stored by the host.
+    fn typed_state(&self, value: Value) -> Option<State> {
+        let value = match &self.options.migrations {
+            Some(_) => migration::split(value).ok()?.1,
+            None => value,
+        };
+        serde_json::from_value(value).ok()
+    }
+}
+
+/// Setters of the `Context` of an invocation.
+macro_rules! context_setters {
+    () => {
+        /// Sets the init state of the subject. Defaults to the current state.
+        pub fn init_state(mut self, init_state: State) -> Self {
+            self.context.init_state = Some(init_state);
+            self
+        }
+
+        /// Sets whether the sender of the invocation is the owner of the subject.
+        pub fn owner(mut self, is_owner: bool) -> Self {
+            self.context.is_owner = is_owner;
+            self
+        }
+
+        /// Sets the public key of the signer of the invocation.
+        pub fn signer(mut self, signer: &str) -> Self {
+            self.context.info.signer = signer.to_owned();
+            self
+        }
+
+        /// Sets the identifier of the subject.
+        pub fn subject_id(mut self, subject_id: &str) -> Self {
+            self.context.info.subject_id = subject_id.to_owned();
+            self
+        }
+
+        /// Sets the identifier of the governance of the subject.
+        pub fn governance_id(mut self, governance_id: &str) -> Self {
+            self.context.info.governance_id = governance_id.to_owned();
+            self
+        }
+
+        /// Sets the sequence number of the event, or of the first event of a batch.
+        pub fn sn(mut self, sn: u64) -> Self {
+            self.context.info.sn = sn;
+            self
+        }
+
+        /// Sets the timestamp of the invocation, in nanoseconds since the Unix epoch.
+        pub fn timestamp(mut self, timestamp: u64) -> Self {
+            self.context.info.timestamp = timestamp;
+            self
+        }
+
+        /// Stores the state with a state version, as returned by a contract with migrations.
+        /// Without it the state is stored unversioned, like an init state checked by
+        /// `check_init_data`. The init state is always stored unversioned.
+        pub fn version(mut self, version: u32) -> Self {
+            self.context.version = Some(version);
+            self
+        }
+
+        /// Sets the execution options.
+        pub fn options(mut self, options: ExecutionOptions<State, Event>) -> Self {
+            self.context.options = options;
+            self
+        }
+    };
+}
+
+/// Contract invocation through the mock host.
+///
+/// ```ignore
+/// let result = Invocation::new(state, StateEvent::One { data: 100 })
+///     .owner(true)
+///     .run(contract_logic);
+/// assert!(result.success);
+/// ```
+pub struct Invocation<State, Event> {
+    context: Context<State, Event>,
+    event: Event,
+}
+
+impl<State, Event> Invocation<State, Event>
+where
+    State: for<'a> Deserialize<'a> + Serialize + Clone,
+    Event: for<'a> Deserialize<'a> + Serialize,
+{
+    /// Creates an invocation of `event` over `state`.
+    pub fn new(state: State, event: Event) -> Self {
+        Self {
+            context: Context::new(state),
+            event,
+        }
+    }
+
+    context_setters!();
+
+    /// Runs `callback` through `execute_contract_with` and decodes its result.
+    ///
+    /// In `ResultMode::Patch` the final state is rebuilt by applying the returned patch over
+    /// the invocation state.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the invocation data can not be encoded or the result can not be decoded.
+    pub fn run<F, M>(self, callback: F) -> ExecutionResult<State>
+    where
+        F: ContractLogic<State, Event, M>,
+    {
+        let context = self.context;
+        let (state_ptr, init_state_ptr) = context.put_states();
+        let event_ptr = put_value(&self.event);
+        let info_ptr = put(&borsh::to_vec(&context.info).expect("Can not serialize context info"));
+        let result_ptr = execute_contract_with(
+            state_ptr,
+            init_state_ptr,
+            event_ptr,
+            context.is_owner as i32,
+            info_ptr,
+            &context.options,
+            callback,
+        );
+        let bytes = get(result_ptr);
+        match context.options.result_mode {
+            ResultMode::FullState => {
+                let result = ContractResultBorsh::try_from_slice(&bytes)
+                    .expect("Can not deserialize contract result");
+                ExecutionResult {
+                    final_state: context.typed_state(result.final_state.0),
+                    success: result.success,
+                    error: result.error,
+                    patch: None,
+                    outputs: result.outputs.into_iter().map(|output| output.0).collect(),
+                }
+            }
+            ResultMode::Patch => {
+                let result = ContractPatchResultBorsh::try_from_slice(&bytes)
+                    .expect("Can not deserialize contract patch result");
+                let mut state = stored_value(&context.state, context.version);
+                let final_state = patch::apply(&mut state, &result.patch)
+                    .ok()
+                    .and_then(|_| context.typed_state(state));
+                ExecutionResult {
+                    final_state,
+                    success: result.success,
+                    error: result.error,
+                    patch: Some(result.patch),
+                    outputs: result.outputs.into_iter().map(|output| output.0).collect(),
+                }
+            }
+        }
+    }
+}
+
+/// Value of `state` as stored by the host, in the envelope of `version` if it is set.
+fn stored_value<State: Serialize>(state: &State, version: Option<u32>) -> Value {
+    let value = serde_json::to_value(state).expect("Can not convert state into Value");
+    match version {
+        Some(version) => migration::wrap(version, value),
+        None => value,
+    }
+}
+
+/// Typed view of the result returned by `execute_contract_batch`.
+#[derive(Debug)]
+pub struct BatchResult<State> {
+    /// Final state after the applied events, `None` if the SDK could not produce one.
+    pub final_state: Option<State>,
+    /// Were all the events applied?
+    pub success: bool,
+    /// Error of the failed event, or of the batch
+    pub error: Option<ContractError>,
+    /// What happened to the batch when an event failed.
+    pub mode: BatchMode,
+    /// Results of the events run, in order.
+    pub events: Vec<EventResult>,
+}
+
+/// Batch of events invocation through the mock host.
+///
+/// The events get consecutive sequence numbers starting at the one set with `sn`.
+///
+/// ```ignore
+/// let result = BatchInvocation::new(state, vec![StateEvent::One { data: 100 }])
+///     .owner(true)
+///     .run(contract_logic);
+/// assert!(result.success);
+/// ```
+pub struct BatchInvocation<State, Event> {
+    context: Context<State, Event>,
+    events: Vec<Event>,
+}
+
+impl<State, Event> BatchInvocation<State, Event>
+where
+    State: for<'a> Deserialize<'a> + Serialize + Clone,
+    Event: for<'a> Deserialize<'a> + Serialize,
+{
+    /// Creates an invocation of `events` over `state`.
+    pub fn new(state: State, events: Vec<Event>) -> Self {
+        Self {
+            context: Context::new(state),
+            events,
+        }
+    }
+
+    context_setters!();
+
+    /// Runs `callback` through `execute_contract_batch` and decodes its result.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the invocation data can not be encoded or the result can not be decoded.
+    pub fn run<F, M>(self, callback: F) -> BatchResult<State>
+    where
+        F: ContractLogic<State, Event, M>,
+    {
+        let context = self.context;
+        let (state_ptr, init_state_ptr) = context.put_states();
+        let events_ptr = put_value(&self.events);
+        let infos: Vec<ContextInfoBorsh> = (0..self.events.len() as u64)
+            .map(|index| ContextInfoBorsh {
+                sn: context.info.sn + index,
+                ..context.info.clone()
+            })
+            .collect();
+        let infos_ptr = put(&borsh::to_vec(&infos).expect("Can not serialize context info"));
+        let result_ptr = execute_contract_batch(
+            state_ptr,
+            init_state_ptr,
+            events_ptr,
+            context.is_owner as i32,
+            infos_ptr,
+            &context.options,
+            callback,
+        );
+        let result = ContractBatchResultBorsh::<ValueWrapper>::try_from_slice(&get(result_ptr))
+            .expect("Can not deserialize contract batch result");
+        BatchResult {
+            final_state: context.typed_state(result.final_state.0),
+            success: result.success,
+            error: result.error,
+            mode: result.mode,
+            events: result.events,
+        }
+    }
+}
+
+/// Runs `callback` through `check_init_data` over `state` and decodes its result.
+///
+/// # Panics
+///
+/// Panics if the state can not be encoded or the result can not be decoded.
+pub fn check_init<State, F, M>(state: &State, callback: F) -> ContractInitCheck
+where
+    State: for<'a> Deserialize<'a> + Serialize + Clone,
+    F: InitLogic<State, M>,
+{
+    let state_ptr = put_value(state);
+    let result_ptr = check_init_data(state_ptr, callback);
+    let result = ContractInitCheckBorsh::try_from_slice(&get(result_ptr))
+        .expect("Can not deserialize init contract result");
+    ContractInitCheck {
+        success: result.success,
+        error: result.error,
+    }
+}
+
+/// Typed view of the result returned by `init_contract`.
+#[derive(Debug)]
+pub struct InitResult<State> {
+    /// Initial state returned by the contract, `None` if it failed or does not match `State`.
+    pub init_state: Option<State>,
+    /// Is the contract init successful?
+    pub success: bool,
+    /// Contract error
+    pub error: Option<ContractError>,
+}
+
+/// Runs `callback` through `init_contract` over the proposed `init_state`, created by
+/// `creator` for `subject_id`, and decodes its result.
+///
+/// # Panics
+///
+/// Panics if the state can not be encoded or the result can not be decoded.
+pub fn run_init<State, F>(
+    init_state: &State,
+    creator: &str,
+    subject_id: &str,
+    callback: F,
+) -> InitResult<State>
+where
+    State: for<'a> Deserialize<'a> + Serialize,
+    F: Fn(&InitContext<State>) -> Result<State, ContractError>,
+{
+    let options = ExecutionOptions::<State, ()>::default();
+    run_init_with(init_state, creator, subject_id, &options, callback)
+}
+
+/// Runs `callback` through `init_contract_with` using `options` and decodes its result. The
+/// initial state is returned without its version envelope, if any.
+///
+/// # Panics
+///
+/// Panics if the state can not be encoded or the result can not be decoded.
+pub fn run_init_with<State, Event, F>(
+    init_state: &State,
+    creator: &str,
+    subject_id: &str,
+    options: &ExecutionOptions<State, Event>,
+    callback: F,
+) -> InitResult<State>
+where
+    State: for<'a> Deserialize<'a> + Serialize,
+    Event: Serialize,
+    F: Fn(&InitContext<State>) -> Result<State, ContractError>,
+{
+    let info = InitInfoBorsh {
+        creator: creator.to_owned(),
+        subject_id: subject_id.to_owned(),
+    };
+    let info_ptr = put(&borsh::to_vec(&info).expect("Can not serialize init info"));
+    let result_ptr = init_contract_with(put_value(init_state), info_ptr, options, callback);
+    let result = ContractInitResultBorsh::<ValueWrapper>::try_from_slice(&get(result_ptr))
+        .expect("Can not deserialize init result");
+    let init_value = match &options.migrations {
+        Some(_) => migration::split(result.init_state.0).ok().map(|(_, value)| value),
+        None => Some(result.init_state.0),
+    };
+    InitResult {
+        init_state: init_value
+            .filter(|_| result.success)
+            .and_then(|value| serde_json::from_value(value).ok()),
+        success: result.success,
+        error: result.error,
+    }
+}
+
+/// Typed view of the result returned by `execute_query`.
+#[derive(Debug)]
+pub struct QueryResult<Response> {
+    /// Response of the query, `None` if it failed or does not match `Response`.
+    pub response: Option<Response>,
+    /// Is the query successful?
+    pub success: bool,
+    /// Query error
+    pub error: Option<ContractError>,
+}
+
+/// Runs `callback` through `execute_query` over `state` and `query` and decodes its result.
+///
+/// # Panics
+///
+/// Panics if the state or the query can not be encoded or the result can not be decoded.
+pub fn run_query<State, Query, Response, F>(
+    state: &State,
+    query: &Query,
+    callback: F,
+) -> QueryResult<Response>
+where
+    State: for<'a> Deserialize<'a> + Serialize,
+    Query: for<'a> Deserialize<'a> + Serialize,
+    Response: for<'a> Deserialize<'a> + Serialize,
+    F: Fn(&State, &Query) -> Result<Response, ContractError>,
+{
+    let result_ptr = execute_query(put_value(state), put_value(query), callback);
+    let result = ContractQueryResultBorsh::<ValueWrapper>::try_from_slice(&get(result_ptr))
+        .expect("Can not deserialize query result");
+    QueryResult {
+        response: result
+            .success
+            .then(|| serde_json::from_value(result.response.0).ok())
+            .flatten(),
+        success: result.success,
+        error: result.error,
+    }
+}
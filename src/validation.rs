@@ -0,0 +1,347 @@
+// Copyright 2025 Kore Ledger
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Validation of values against a JSON Schema.
+//!
+//! Only the keywords that describe the shape of the data are checked: `type`, `enum`, `const`,
+//! `properties`, `required`, `additionalProperties`, `items`, `prefixItems`, `minItems`,
+//! `maxItems`, `uniqueItems`, `minimum`, `maximum`, `exclusiveMinimum`, `exclusiveMaximum`,
+//! `minLength`, `maxLength`, `allOf`, `anyOf`, `oneOf`, `not` and local `$ref` references.
+//! Other keywords, such as `format` or `pattern`, are ignored.
+
+use serde_json::{Map, Value};
+
+use crate::error::ValidationError;
+
+/// Validates `value` against `schema`.
+pub fn validate(schema: &Value, value: &Value) -> Result<(), ValidationError> {
+    Validator { root: schema }.validate(schema, value, &mut String::new())
+}
+
+struct Validator<'a> {
+    root: &'a Value,
+}
+
+impl<'a> Validator<'a> {
+    fn validate(
+        &self,
+        schema: &'a Value,
+        value: &Value,
+        path: &mut String,
+    ) -> Result<(), ValidationError> {
+        let schema = match schema {
+            Value::Bool(true) => return Ok(()),
+            Value::Bool(false) => return Err(error(path, "No value is allowed")),
+            Value::Object(schema) => schema,
+            _ => return Err(error(path, "Invalid schema")),
+        };
+        if let Some(Value::String(reference)) = schema.get("$ref") {
+            self.validate(self.resolve(reference, path)?, value, path)?;
+        }
+        if let Some(types) = schema.get("type") {
+            check_type(types, value, path)?;
+        }
+        if let Some(Value::Array(values)) = schema.get("enum") {
+            if !values.contains(value) {
+                return Err(error(path, "Value is not one of the allowed values"));
+            }
+        }
+        if let Some(constant) = schema.get("const") {
+            if constant != value {
+                return Err(error(path, format!("Value must be {}", constant)));
+            }
+        }
+        match value {
+            Value::Object(object) => self.validate_object(schema, object, path)?,
+            Value::Array(array) => self.validate_array(schema, array, path)?,
+            Value::String(string) => validate_string(schema, string, path)?,
+            Value::Number(_) => validate_number(schema, value, path)?,
+            _ => {}
+        }
+        self.validate_combinators(schema, value, path)
+    }
+
+    fn resolve(&self, reference: &str, path: &str) -> Result<&'a Value, ValidationError> {
+        reference
+            .strip_prefix('#')
+            .and_then(|pointer| self.root.pointer(pointer))
+            .ok_or_else(|| error(path, format!("Unresolved schema reference {}", reference)))
+    }
+
+    fn validate_object(
+        &self,
+        schema: &'a Map<String, Value>,
+        object: &Map<String, Value>,
+        path: &mut String,
+    ) -> Result<(), ValidationError> {
+        if let Some(Value::Array(required)) = schema.get("required") {
+            for key in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(key) {
+                    return Err(error(path, format!("Missing required property {}", key)));
+                }
+            }
+        }
+        let properties = schema.get("properties").and_then(Value::as_object);
+        let additional = schema.get("additionalProperties");
+        for (key, property) in object {
+            let len = path.len();
+            path.push('/');
+            path.push_str(&key.replace('~', "~0").replace('/', "~1"));
+            match properties.and_then(|properties| properties.get(key)) {
+                Some(property_schema) => self.validate(property_schema, property, path)?,
+                None => {
+                    if let Some(additional) = additional {
+                        self.validate(additional, property, path)?;
+                    }
+                }
+            }
+            path.truncate(len);
+        }
+        Ok(())
+    }
+
+    fn validate_array(
+        &self,
+        schema: &'a Map<String, Value>,
+        array: &[Value],
+        path: &mut String,
+    ) -> Result<(), ValidationError> {
+        if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+            if (array.len() as u64) < min {
+                return Err(error(path, format!("Expected at least {} items", min)));
+            }
+        }
+        if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+            if array.len() as u64 > max {
+                return Err(error(path, format!("Expected at most {} items", max)));
+            }
+        }
+        if schema.get("uniqueItems") == Some(&Value::Bool(true)) {
+            for (index, item) in array.iter().enumerate() {
+                if array[..index].contains(item) {
+                    return Err(error(path, "Items must be unique"));
+                }
+            }
+        }
+        let prefix = schema.get("prefixItems").and_then(Value::as_array);
+        let prefix_len = prefix.map_or(0, Vec::len);
+        for (index, item) in array.iter().enumerate() {
+            let item_schema = match prefix.and_then(|prefix| prefix.get(index)) {
+                Some(item_schema) => item_schema,
+                None => match schema.get("items") {
+                    Some(item_schema) if index >= prefix_len => item_schema,
+                    _ => continue,
+                },
+            };
+            let len = path.len();
+            path.push('/');
+            path.push_str(&index.to_string());
+            self.validate(item_schema, item, path)?;
+            path.truncate(len);
+        }
+        Ok(())
+    }
+
+    fn validate_combinators(
+        &self,
+        schema: &'a Map<String, Value>,
+        value: &Value,
+        path: &mut String,
+    ) -> Result<(), ValidationError> {
+        if let Some(Value::Array(schemas)) = schema.get("allOf") {
+            for schema in schemas {
+                self.validate(schema, value, path)?;
+            }
+        }
+        if let Some(Value::Array(schemas)) = schema.get("anyOf") {
+            if !schemas
+                .iter()
+                .any(|schema| self.is_valid(schema, value, path))
+            {
+                return Err(error(
+                    path,
+                    "Value does not match any of the allowed schemas",
+                ));
+            }
+        }
+        if let Some(Value::Array(schemas)) = schema.get("oneOf") {
+            let matches = schemas
+                .iter()
+                .filter(|schema| self.is_valid(schema, value, path))
+                .count();
+            if matches != 1 {
+                return Err(error(
+                    path,
+                    "Value must match exactly one of the allowed schemas",
+                ));
+            }
+        }
+        if let Some(not) = schema.get("not") {
+            if self.is_valid(not, value, path) {
+                return Err(error(path, "Value matches a forbidden schema"));
+            }
+        }
+        Ok(())
+    }
+
+    fn is_valid(&self, schema: &'a Value, value: &Value, path: &str) -> bool {
+        self.validate(schema, value, &mut path.to_owned()).is_ok()
+    }
+}
+
+fn check_type(types: &Value, value: &Value, path: &str) -> Result<(), ValidationError> {
+    let matches = |name: &Value| match name.as_str() {
+        Some("null") => value.is_null(),
+        Some("boolean") => value.is_boolean(),
+        Some("object") => value.is_object(),
+        Some("array") => value.is_array(),
+        Some("string") => value.is_string(),
+        Some("number") => value.is_number(),
+        Some("integer") => is_integer(value),
+        _ => false,
+    };
+    let valid = match types {
+        Value::Array(types) => types.iter().any(matches),
+        name => matches(name),
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(error(path, format!("Expected type {}", types)))
+    }
+}
+
+fn is_integer(value: &Value) -> bool {
+    match value {
+        Value::Number(number) if number.is_f64() => {
+            number.as_f64().is_some_and(|number| number.fract() == 0.0)
+        }
+        Value::Number(_) => true,
+        _ => false,
+    }
+}
+
+fn validate_string(
+    schema: &Map<String, Value>,
+    string: &str,
+    path: &str,
+) -> Result<(), ValidationError> {
+    let len = string.chars().count() as u64;
+    if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+        if len < min {
+            return Err(error(path, format!("Expected at least {} characters", min)));
+        }
+    }
+    if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+        if len > max {
+            return Err(error(path, format!("Expected at most {} characters", max)));
+        }
+    }
+    Ok(())
+}
+
+fn validate_number(
+    schema: &Map<String, Value>,
+    value: &Value,
+    path: &str,
+) -> Result<(), ValidationError> {
+    let Some(number) = value.as_f64() else {
+        return Ok(());
+    };
+    let bound = |keyword: &str| schema.get(keyword).and_then(Value::as_f64);
+    if let Some(minimum) = bound("minimum") {
+        if number < minimum {
+            return Err(error(path, format!("Value must be at least {}", minimum)));
+        }
+    }
+    if let Some(maximum) = bound("maximum") {
+        if number > maximum {
+            return Err(error(path, format!("Value must be at most {}", maximum)));
+        }
+    }
+    if let Some(minimum) = bound("exclusiveMinimum") {
+        if number <= minimum {
+            return Err(error(
+                path,
+                format!("Value must be greater than {}", minimum),
+            ));
+        }
+    }
+    if let Some(maximum) = bound("exclusiveMaximum") {
+        if number >= maximum {
+            return Err(error(path, format!("Value must be less than {}", maximum)));
+        }
+    }
+    Ok(())
+}
+
+fn error(path: &str, message: impl Into<String>) -> ValidationError {
+    ValidationError {
+        path: path.to_owned(),
+        message: message.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "status": { "$ref": "#/$defs/Status" },
+                "amount": { "type": "integer", "minimum": 0 },
+                "tags": { "type": "array", "items": { "type": "string", "maxLength": 3 } },
+                "owner": { "type": ["string", "null"] }
+            },
+            "required": ["status", "amount"],
+            "additionalProperties": false,
+            "$defs": {
+                "Status": { "oneOf": [{ "const": "Draft" }, { "const": "Closed" }] }
+            }
+        });
+        let valid = json!({ "status": "Draft", "amount": 3, "tags": ["a"], "owner": null });
+        assert_eq!(validate(&schema, &valid), Ok(()));
+
+        let cases = [
+            (
+                json!({ "status": "Draft" }),
+                "",
+                "Missing required property amount",
+            ),
+            (
+                json!({ "status": "Open", "amount": 1 }),
+                "/status",
+                "exactly one",
+            ),
+            (
+                json!({ "status": "Draft", "amount": -1 }),
+                "/amount",
+                "at least 0",
+            ),
+            (
+                json!({ "status": "Draft", "amount": 1.5 }),
+                "/amount",
+                "Expected type",
+            ),
+            (
+                json!({ "status": "Draft", "amount": 1, "tags": ["long"] }),
+                "/tags/0",
+                "at most",
+            ),
+            (
+                json!({ "status": "Draft", "amount": 1, "extra": 1 }),
+                "/extra",
+                "No value",
+            ),
+        ];
+        for (value, path, message) in cases {
+            let error = validate(&schema, &value).unwrap_err();
+            assert_eq!(error.path, path);
+            assert!(error.message.contains(message), "{}", error.message);
+        }
+    }
+}
@@ -7,155 +7,340 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Number, Value};
 
+use crate::{bytes, de::DecodeLimits, error::DecodeError};
+
 /// Wrapper for `serde_json::Value` that implements `BorshSerialize` and `BorshDeserialize`.
+///
+/// Byte arrays encoded with the bytes tag are decoded as `{ "$bytes": "<base64>" }` objects,
+/// which are encoded as any other object, and numbers encoded with the numeric tag as their
+/// string.
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
 pub struct ValueWrapper(pub Value);
 
+impl ValueWrapper {
+    /// Returns the value with its numbers normalized, see `ValueWrapper::normalize`.
+    pub fn canonical(mut self) -> Self {
+        normalize(&mut self.0);
+        self
+    }
+
+    /// Normalizes every number to a single representation: floats without a fractional part
+    /// that fit in `i64` or `u64`, including `-0.0`, become integers, and integers are encoded
+    /// as `i64` unless they only fit in `u64`.
+    ///
+    /// The borsh encoding of a normalized value is canonical: equal values always produce the
+    /// same bytes.
+    pub fn normalize(&mut self) {
+        normalize(&mut self.0);
+    }
+
+    /// Canonical borsh encoding of the value.
+    pub fn to_canonical_vec(&self) -> std::io::Result<Vec<u8>> {
+        borsh::to_vec(&self.clone().canonical())
+    }
+
+    /// Decodes a value and rejects it unless `bytes` is its canonical encoding, i.e. object
+    /// keys are sorted and unique and numbers are normalized.
+    pub fn from_canonical_slice(bytes: &[u8]) -> std::io::Result<Self> {
+        let value = Self::try_from_slice(bytes)?;
+        if value.to_canonical_vec()? != bytes {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Value is not canonically encoded",
+            ));
+        }
+        Ok(value)
+    }
+}
+
+pub(crate) fn normalize(value: &mut Value) {
+    match value {
+        Value::Number(number) => {
+            if let Some(float) = float_value(number) {
+                if let Some(integer) = integral(float) {
+                    *number = integer;
+                }
+            }
+        }
+        Value::Array(array) => array.iter_mut().for_each(normalize),
+        Value::Object(object) => object.values_mut().for_each(normalize),
+        _ => {}
+    }
+}
+
+/// The `f64` that serde_json gives to visitors for `number`: numbers that are not integers of
+/// up to 128 bits and are spelled like that `f64`. Other numbers are only exact as literals.
+pub(crate) fn float_value(number: &Number) -> Option<f64> {
+    if number.as_i128().is_some() || number.as_u128().is_some() {
+        return None;
+    }
+    let float = number.as_f64()?;
+    let literal = number.to_string();
+    let exact = Number::from_f64(float).is_some_and(|exact| exact.to_string() == literal);
+    (exact || float.to_string() == literal).then_some(float)
+}
+
+/// Decodes a number literal. This decides the number both decoders give to a literal: the
+/// integer for integers of up to 128 bits, the `f64` for literals with its spelling, and the
+/// literal itself for any other number.
+pub(crate) fn number_literal(literal: &str) -> Result<Number, DecodeError> {
+    let Ok(number) = literal.parse::<Number>() else {
+        return Err(DecodeError::InvalidNumber(literal.to_owned()));
+    };
+    if let Some(float) = float_value(&number) {
+        Ok(Number::from_f64(float).unwrap_or(number))
+    } else if let Some(integer) = number.as_i128() {
+        Ok(Number::from_i128(integer).unwrap_or(number))
+    } else {
+        Ok(number)
+    }
+}
+
+/// Decodes a number of the SDK number types, written with its own tag. It is given as the
+/// string those types serialize to, so it is the same value as without the tag.
+pub(crate) fn numeric_literal(literal: &str) -> Result<&str, DecodeError> {
+    match literal.parse::<Number>() {
+        Ok(_) => Ok(literal),
+        Err(_) => Err(DecodeError::InvalidNumber(literal.to_owned())),
+    }
+}
+
+/// Integer with the value of `float`, if it has no fractional part and fits in 64 bits.
+pub(crate) fn integral(float: f64) -> Option<Number> {
+    const I64_MIN: f64 = -9_223_372_036_854_775_808.0;
+    const I64_END: f64 = 9_223_372_036_854_775_808.0;
+    const U64_END: f64 = 18_446_744_073_709_551_616.0;
+    if float.fract() != 0.0 {
+        None
+    } else if (I64_MIN..I64_END).contains(&float) {
+        Some(Number::from(float as i64))
+    } else if (0.0..U64_END).contains(&float) {
+        Some(Number::from(float as u64))
+    } else {
+        None
+    }
+}
+
 /// Borsh serialization implementation for `ValueWrapper`.
+///
+/// Object keys are always written in ascending byte order, so the encoding does not depend on
+/// the iteration order of `serde_json::Map`.
 impl BorshSerialize for ValueWrapper {
     #[inline]
     fn serialize<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
-        match &self.0 {
-            Value::Bool(data) => {
-                BorshSerialize::serialize(&0u8, writer)?;
-                BorshSerialize::serialize(&data, writer)
-            }
-            Value::Number(data) => {
+        serialize_value(&self.0, writer)
+    }
+}
+
+pub(crate) fn serialize_value<W: Write>(value: &Value, writer: &mut W) -> std::io::Result<()> {
+    match value {
+        Value::Bool(data) => {
+            BorshSerialize::serialize(&0u8, writer)?;
+            BorshSerialize::serialize(&data, writer)
+        }
+        Value::Number(data) => {
+            BorshSerialize::serialize(&1u8, writer)?;
+            if let Some(data) = data.as_i64() {
                 BorshSerialize::serialize(&1u8, writer)?;
-                'data: {
-                    if data.is_f64() {
-                        let Some(data) = data.as_f64() else {
-                            break 'data;
-                        };
-                        BorshSerialize::serialize(&0u8, writer)?;
-                        return BorshSerialize::serialize(&data, writer);
-                    } else if data.is_i64() {
-                        let Some(data) = data.as_i64() else {
-                            break 'data;
-                        };
-                        BorshSerialize::serialize(&1u8, writer)?;
-                        return BorshSerialize::serialize(&data, writer);
-                    } else if data.is_u64() {
-                        let Some(data) = data.as_u64() else {
-                            break 'data;
-                        };
-                        BorshSerialize::serialize(&2u8, writer)?;
-                        return BorshSerialize::serialize(&data, writer);
-                    }
-                }
-                Err(std::io::Error::new(
-                    std::io::ErrorKind::InvalidData,
-                    "Invalid number type",
-                ))
-            }
-            Value::String(data) => {
+                BorshSerialize::serialize(&data, writer)
+            } else if let Some(data) = data.as_u64() {
                 BorshSerialize::serialize(&2u8, writer)?;
                 BorshSerialize::serialize(&data, writer)
-            }
-            Value::Array(data) => {
+            } else if let Some(float) = float_value(data) {
+                BorshSerialize::serialize(&0u8, writer)?;
+                BorshSerialize::serialize(&float, writer)
+            } else {
                 BorshSerialize::serialize(&3u8, writer)?;
-                BorshSerialize::serialize(&(data.len() as u32), writer)?;
-                for element in data {
-                    let element = ValueWrapper(element.to_owned());
-                    BorshSerialize::serialize(&element, writer)?;
-                }
-                Ok(())
+                BorshSerialize::serialize(&data.to_string(), writer)
             }
-            Value::Object(data) => {
-                BorshSerialize::serialize(&4u8, writer)?;
-                BorshSerialize::serialize(&(data.len() as u32), writer)?;
-                for (key, value) in data {
-                    BorshSerialize::serialize(&key, writer)?;
-                    let value = ValueWrapper(value.to_owned());
-                    BorshSerialize::serialize(&value, writer)?;
-                }
-                Ok(())
+        }
+        Value::String(data) => {
+            BorshSerialize::serialize(&2u8, writer)?;
+            BorshSerialize::serialize(&data, writer)
+        }
+        Value::Array(data) => {
+            BorshSerialize::serialize(&3u8, writer)?;
+            BorshSerialize::serialize(&(data.len() as u32), writer)?;
+            for element in data {
+                serialize_value(element, writer)?;
+            }
+            Ok(())
+        }
+        Value::Object(data) => {
+            BorshSerialize::serialize(&4u8, writer)?;
+            BorshSerialize::serialize(&(data.len() as u32), writer)?;
+            let mut entries: Vec<_> = data.iter().collect();
+            entries.sort_unstable_by_key(|(key, _)| *key);
+            for (key, value) in entries {
+                BorshSerialize::serialize(key, writer)?;
+                serialize_value(value, writer)?;
             }
-            Value::Null => BorshSerialize::serialize(&5u8, writer),
+            Ok(())
         }
+        Value::Null => BorshSerialize::serialize(&5u8, writer),
     }
 }
 
-/// Borsh deserialization implementation for `ValueWrapper`.
+/// Borsh deserialization implementation for `ValueWrapper`, with the default `DecodeLimits`.
 impl BorshDeserialize for ValueWrapper {
     #[inline]
     fn deserialize_reader<R: Read>(reader: &mut R) -> std::io::Result<Self> {
-        let order: u8 = BorshDeserialize::deserialize_reader(reader)?;
-        match order {
-            0 => {
-                let data: bool = BorshDeserialize::deserialize_reader(reader)?;
-                Ok(ValueWrapper(Value::Bool(data)))
-            }
-            1 => {
-                let internal_order: u8 =
-                    BorshDeserialize::deserialize_reader(reader)?;
-                match internal_order {
-                    0 => {
-                        let data: f64 =
-                            BorshDeserialize::deserialize_reader(reader)?;
-                        let Some(data_f64) = Number::from_f64(data) else {
-                            return Err(std::io::Error::new(
-                                std::io::ErrorKind::InvalidInput,
-                                format!("Invalid f64 Number: {}", data),
-                            ));
-                        };
-                        Ok(ValueWrapper(Value::Number(data_f64)))
-                    }
-                    1 => {
-                        let data: i64 =
-                            BorshDeserialize::deserialize_reader(reader)?;
-                        Ok(ValueWrapper(Value::Number(Number::from(data))))
-                    }
-                    2 => {
-                        let data: u64 =
-                            BorshDeserialize::deserialize_reader(reader)?;
-                        Ok(ValueWrapper(Value::Number(Number::from(data))))
-                    }
-                    _ => Err(std::io::Error::new(
-                        std::io::ErrorKind::InvalidInput,
-                        format!(
-                            "Invalid Number representation: {}",
-                            internal_order
-                        ),
-                    )),
+        let value = Decoder::new(reader, DecodeLimits::default()).value()?;
+        Ok(ValueWrapper(value))
+    }
+}
+
+impl ValueWrapper {
+    /// Decodes a value from its borsh encoding, rejecting input that exceeds `limits`.
+    pub fn from_slice_with_limits(bytes: &[u8], limits: DecodeLimits) -> Result<Self, DecodeError> {
+        let mut reader = bytes;
+        let value = Decoder::new(&mut reader, limits).value()?;
+        if !reader.is_empty() {
+            return Err(DecodeError::TrailingBytes);
+        }
+        Ok(ValueWrapper(value))
+    }
+}
+
+/// Decoder of the `ValueWrapper` encoding that enforces `DecodeLimits`. Lengths are checked
+/// against the limits before anything is allocated.
+struct Decoder<'a, R> {
+    reader: &'a mut R,
+    limits: DecodeLimits,
+    consumed: usize,
+    depth: usize,
+}
+
+impl<'a, R: Read> Decoder<'a, R> {
+    fn new(reader: &'a mut R, limits: DecodeLimits) -> Self {
+        Self {
+            reader,
+            limits,
+            consumed: 0,
+            depth: 0,
+        }
+    }
+
+    fn consume(&mut self, len: usize) -> Result<(), DecodeError> {
+        self.consumed = self.consumed.saturating_add(len);
+        if self.consumed > self.limits.max_size {
+            return Err(DecodeError::SizeLimit(self.limits.max_size));
+        }
+        Ok(())
+    }
+
+    fn read<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        self.consume(N)?;
+        let mut bytes = [0; N];
+        self.reader.read_exact(&mut bytes).map_err(read_error)?;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.read::<1>()?[0])
+    }
+
+    /// Length of an array or object.
+    fn read_len(&mut self) -> Result<usize, DecodeError> {
+        let len = u32::from_le_bytes(self.read()?) as usize;
+        if len > self.limits.max_elements {
+            return Err(DecodeError::ElementLimit(self.limits.max_elements));
+        }
+        Ok(len)
+    }
+
+    fn read_string(&mut self) -> Result<String, DecodeError> {
+        String::from_utf8(self.read_bytes()?).map_err(|_| DecodeError::InvalidUtf8)
+    }
+
+    fn read_bytes(&mut self) -> Result<Vec<u8>, DecodeError> {
+        let len = u32::from_le_bytes(self.read()?) as usize;
+        if len > self.limits.max_string_len {
+            return Err(DecodeError::StringLimit(self.limits.max_string_len));
+        }
+        self.consume(len)?;
+        let mut bytes = Vec::with_capacity(len);
+        let read = (&mut *self.reader)
+            .take(len as u64)
+            .read_to_end(&mut bytes)
+            .map_err(read_error)?;
+        if read < len {
+            return Err(DecodeError::UnexpectedEnd);
+        }
+        Ok(bytes)
+    }
+
+    /// Enters a nested array or object.
+    fn enter(&mut self) -> Result<(), DecodeError> {
+        if self.depth >= self.limits.max_depth {
+            return Err(DecodeError::DepthLimit(self.limits.max_depth));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn value(&mut self) -> Result<Value, DecodeError> {
+        match self.read_u8()? {
+            0 => match self.read_u8()? {
+                0 => Ok(Value::Bool(false)),
+                1 => Ok(Value::Bool(true)),
+                byte => Err(DecodeError::InvalidBool(byte)),
+            },
+            1 => match self.read_u8()? {
+                0 => {
+                    let data = f64::from_le_bytes(self.read()?);
+                    let Some(data) = Number::from_f64(data) else {
+                        return Err(DecodeError::InvalidNumber(data.to_string()));
+                    };
+                    Ok(Value::Number(data))
                 }
-            }
-            2 => {
-                let data: String =
-                    BorshDeserialize::deserialize_reader(reader)?;
-                Ok(ValueWrapper(Value::String(data)))
-            }
+                1 => Ok(Value::Number(i64::from_le_bytes(self.read()?).into())),
+                2 => Ok(Value::Number(u64::from_le_bytes(self.read()?).into())),
+                3 => Ok(Value::Number(number_literal(&self.read_string()?)?)),
+                tag => Err(DecodeError::InvalidNumberTag(tag)),
+            },
+            2 => Ok(Value::String(self.read_string()?)),
             3 => {
-                let len = u32::deserialize_reader(reader)?;
-                if len == 0 {
-                    Ok(ValueWrapper(Value::Array(Vec::new())))
-                } else {
-                    let mut result = Vec::with_capacity(len as usize);
-                    for _ in 0..len {
-                        result
-                            .push(ValueWrapper::deserialize_reader(reader)?.0);
-                    }
-                    Ok(ValueWrapper(Value::Array(result)))
+                let len = self.read_len()?;
+                self.enter()?;
+                // The length comes from the input, so it is not trusted for preallocation.
+                let mut result = Vec::with_capacity(len.min(4096));
+                for _ in 0..len {
+                    result.push(self.value()?);
                 }
+                self.depth -= 1;
+                Ok(Value::Array(result))
             }
             4 => {
-                let len = u32::deserialize_reader(reader)?;
+                let len = self.read_len()?;
+                self.enter()?;
                 let mut result = Map::new();
                 for _ in 0..len {
-                    let key = String::deserialize_reader(reader)?;
-                    let value = ValueWrapper::deserialize_reader(reader)?;
-                    result.insert(key, value.0);
+                    let key = self.read_string()?;
+                    if result.contains_key(&key) {
+                        return Err(DecodeError::DuplicateKey(key));
+                    }
+                    let value = self.value()?;
+                    result.insert(key, value);
                 }
-                Ok(ValueWrapper(Value::Object(result)))
+                self.depth -= 1;
+                Ok(Value::Object(result))
             }
-            5 => Ok(ValueWrapper(Value::Null)),
-            _ => Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                format!("Invalid Value representation: {}", order),
-            )),
+            5 => Ok(Value::Null),
+            6 => Ok(bytes::to_value(&self.read_bytes()?)),
+            7 => Ok(Value::String(numeric_literal(&self.read_string()?)?.to_owned())),
+            tag => Err(DecodeError::InvalidTag(tag)),
         }
     }
 }
+
+fn read_error(error: std::io::Error) -> DecodeError {
+    match error.kind() {
+        std::io::ErrorKind::UnexpectedEof => DecodeError::UnexpectedEnd,
+        _ => DecodeError::Message(error.to_string()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -167,4 +352,121 @@ mod tests {
         let value2: ValueWrapper = BorshDeserialize::try_from_slice(&vec).unwrap();
         assert_eq!(value, value2);
     }
+
+    #[test]
+    fn test_canonical_encoding() {
+        let mut map = Map::new();
+        map.insert("b".to_owned(), Value::from(-0.0));
+        map.insert("a".to_owned(), Value::from(2.0));
+        let value = ValueWrapper(Value::Object(map));
+        let expected = [
+            4, 2, 0, 0, 0, // object of 2 entries
+            1, 0, 0, 0, b'a', 1, 1, 2, 0, 0, 0, 0, 0, 0, 0, // "a": 2
+            1, 0, 0, 0, b'b', 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, // "b": 0
+        ];
+        assert_eq!(value.to_canonical_vec().unwrap(), expected);
+        assert_eq!(
+            ValueWrapper::from_canonical_slice(&expected).unwrap(),
+            value.clone().canonical()
+        );
+
+        // Plain encoding keeps the floats but still sorts the keys.
+        let plain = borsh::to_vec(&value).unwrap();
+        assert_eq!(&plain[5..10], &[1, 0, 0, 0, b'a']);
+        assert!(ValueWrapper::from_canonical_slice(&plain).is_err());
+
+        let unsorted = [&expected[..5], &expected[20..], &expected[5..20]].concat();
+        assert!(ValueWrapper::try_from_slice(&unsorted).is_ok());
+        assert!(ValueWrapper::from_canonical_slice(&unsorted).is_err());
+
+        let duplicated = [&expected[..20], &expected[5..20]].concat();
+        let error = ValueWrapper::try_from_slice(&duplicated).unwrap_err();
+        assert_eq!(error.to_string(), "Duplicate object key: a");
+    }
+
+    #[test]
+    fn test_number_literal() {
+        let literal = crate::U256::MAX.to_string();
+        let bytes = literal_bytes(&literal);
+        let value = ValueWrapper::try_from_slice(&bytes).unwrap();
+        let number: crate::U256 = serde_json::from_value(value.0.clone()).unwrap();
+        assert_eq!(number, crate::U256::MAX);
+        assert_eq!(value.0.to_string(), literal);
+        assert_eq!(borsh::to_vec(&value).unwrap(), bytes);
+
+        assert!(ValueWrapper::try_from_slice(&literal_bytes("1e")).is_err());
+    }
+
+    #[test]
+    fn test_number_literal_decoders() {
+        let literals = [
+            ("5", "5"),
+            ("-0", "0"),
+            ("1.5", "1.5"),
+            ("1.50", "1.50"),
+            ("1e400", "1e+400"),
+            ("1E5", "1e+5"),
+            ("340282366920938463463374607431768211455", "340282366920938463463374607431768211455"),
+            ("1000000000000000000000000000000000000000", "1e+39"),
+            (
+                "-12345678901234567890123456789012345678901",
+                "-12345678901234567890123456789012345678901",
+            ),
+        ];
+        for (literal, decoded) in literals {
+            let bytes = literal_bytes(literal);
+            let value = ValueWrapper::try_from_slice(&bytes).unwrap().0;
+            assert_eq!(crate::from_bytes::<Value>(&bytes).unwrap(), value, "{}", literal);
+            assert_eq!(value.to_string(), decoded, "{}", literal);
+            assert!(value.is_number(), "{}", literal);
+        }
+
+        // Both paths give the same integer to the contract types.
+        let bytes = literal_bytes("5");
+        let value = ValueWrapper::try_from_slice(&bytes).unwrap().0;
+        assert_eq!(serde_json::from_value::<u8>(value).unwrap(), 5);
+        assert_eq!(crate::from_bytes::<u8>(&bytes).unwrap(), 5);
+
+        // Numbers with the numeric tag are their string.
+        let numeric = [7, 3, 0, 0, 0, b'2', b'.', b'5'];
+        let value = ValueWrapper::try_from_slice(&numeric).unwrap().0;
+        assert_eq!(value, Value::String("2.5".to_owned()));
+        assert_eq!(crate::from_bytes::<Value>(&numeric).unwrap(), value);
+        let invalid = [7, 1, 0, 0, 0, b'x'];
+        assert!(ValueWrapper::try_from_slice(&invalid).is_err());
+        assert!(crate::from_bytes::<Value>(&invalid).is_err());
+    }
+
+    fn literal_bytes(literal: &str) -> Vec<u8> {
+        let mut bytes = vec![1, 3];
+        bytes.extend(borsh::to_vec(literal).unwrap());
+        bytes
+    }
+
+    #[test]
+    fn test_decode_limits() {
+        let limits = DecodeLimits {
+            max_depth: 2,
+            max_elements: 3,
+            max_string_len: 4,
+            max_size: 32,
+        };
+        let decode = |bytes: &[u8]| ValueWrapper::from_slice_with_limits(bytes, limits);
+        let nested = |depth: usize| [3, 1, 0, 0, 0].repeat(depth).into_iter().chain([5]);
+
+        assert!(decode(&nested(2).collect::<Vec<_>>()).is_ok());
+        assert_eq!(decode(&nested(3).collect::<Vec<_>>()), Err(DecodeError::DepthLimit(2)));
+        // The length prefix is rejected before allocating anything.
+        assert_eq!(decode(&[3, 255, 255, 255, 255]), Err(DecodeError::ElementLimit(3)));
+        assert_eq!(decode(&[2, 5, 0, 0, 0]), Err(DecodeError::StringLimit(4)));
+        let large = [[3, 3, 0, 0, 0].as_slice(), &[1, 1, 0, 0, 0, 0, 0, 0, 0, 0].repeat(3)].concat();
+        assert_eq!(decode(&large), Err(DecodeError::SizeLimit(32)));
+        assert_eq!(decode(&[2, 4, 0, 0, 0, b'a']), Err(DecodeError::UnexpectedEnd));
+        assert_eq!(decode(&[5, 5]), Err(DecodeError::TrailingBytes));
+
+        // The borsh implementation applies the default limits.
+        let deep: Vec<u8> = nested(10_000).collect();
+        let error = ValueWrapper::try_from_slice(&deep).unwrap_err();
+        assert_eq!(error.to_string(), "Nesting depth exceeds the limit of 128");
+    }
 }